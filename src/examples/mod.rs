@@ -0,0 +1,151 @@
+use std::{fs, path::Path};
+
+use regex::Regex;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Examples {
+    pub blocks: Vec<String>,
+    pub answers: Vec<String>,
+}
+
+pub fn extract(html: &str) -> Examples {
+    let block_regex = Regex::new(r"(?s)<pre><code>(.*?)</code></pre>").unwrap();
+    // Answers are emphasised inline code, written either way round in the puzzle text
+    let answer_regex =
+        Regex::new(r"(?s)<code><em>(.*?)</em></code>|<em><code>(.*?)</code></em>").unwrap();
+
+    let blocks = block_regex
+        .captures_iter(html)
+        .map(|capture| unescape(&strip_tags(&capture[1])))
+        .collect();
+
+    let answers = answer_regex
+        .captures_iter(html)
+        .map(|capture| capture.get(1).or(capture.get(2)).unwrap().as_str())
+        .map(|answer| unescape(&strip_tags(answer)))
+        .collect();
+
+    Examples { blocks, answers }
+}
+
+fn strip_tags(html: &str) -> String {
+    // Code blocks highlight parts of the example with <em>, which aren't part of the input
+    let tag_regex = Regex::new(r"<[^>]*>").unwrap();
+    tag_regex.replace_all(html, "").to_string()
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+// Writes the chosen main block (1-indexed) as test_input.txt and the rest in page order as
+// test_input_2.txt, test_input_3.txt, and so on, the same names the tests already read.
+// Existing files are often hand-edited fixtures, so nothing is written if any of them exist
+// unless forced
+pub fn write_examples(
+    dir: &Path,
+    examples: &Examples,
+    main_block: usize,
+    force: bool,
+) -> Result<(), String> {
+    if main_block == 0 || main_block > examples.blocks.len() {
+        return Err(format!(
+            "Main block {main_block} out of range, found {} blocks",
+            examples.blocks.len()
+        ));
+    }
+
+    let others = examples
+        .blocks
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| index + 1 != main_block)
+        .map(|(_, block)| block);
+    let files: Vec<(String, &str)> = std::iter::once(&examples.blocks[main_block - 1])
+        .chain(others)
+        .enumerate()
+        .map(|(index, block)| match index {
+            0 => ("test_input.txt".to_string(), block.as_str()),
+            _ => (format!("test_input_{}.txt", index + 1), block.as_str()),
+        })
+        .collect();
+
+    if !force {
+        if let Some((name, _)) = files.iter().find(|(name, _)| dir.join(name).exists()) {
+            return Err(format!(
+                "{} already exists, pass --force to overwrite it",
+                dir.join(name).display()
+            ));
+        }
+    }
+
+    fs::create_dir_all(dir).map_err(|error| error.to_string())?;
+    for (name, contents) in files {
+        fs::write(dir.join(name), contents).map_err(|error| error.to_string())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = "<article><p>For example:</p>
+<pre><code>1abc2
+pqr3stu8vwx
+</code></pre>
+<p>Adding these together produces <code><em>142</em></code>.</p>
+<pre><code>a &lt;- <em>b</em> &amp;&amp; c
+</code></pre>
+<p>This time the answer is <em><code>x&gt;2</code></em>.</p></article>";
+
+    #[test]
+    fn test_extract() {
+        let examples = extract(PAGE);
+
+        assert_eq!(
+            examples,
+            Examples {
+                blocks: vec![
+                    "1abc2\npqr3stu8vwx\n".to_string(),
+                    "a <- b && c\n".to_string()
+                ],
+                answers: vec!["142".to_string(), "x>2".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_write_examples() {
+        let dir = std::env::temp_dir().join("aoc_examples_test");
+        let _ = fs::remove_dir_all(&dir);
+        let examples = extract(PAGE);
+
+        assert!(write_examples(&dir, &examples, 3, false).is_err());
+        write_examples(&dir, &examples, 1, false).unwrap();
+
+        // The files written first are kept unless forced
+        assert!(write_examples(&dir, &examples, 2, false)
+            .unwrap_err()
+            .contains("already exists"));
+        assert_eq!(
+            fs::read_to_string(dir.join("test_input.txt")).unwrap(),
+            "1abc2\npqr3stu8vwx\n"
+        );
+        write_examples(&dir, &examples, 2, true).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(dir.join("test_input.txt")).unwrap(),
+            "a <- b && c\n"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("test_input_2.txt")).unwrap(),
+            "1abc2\npqr3stu8vwx\n"
+        );
+    }
+}
@@ -0,0 +1,338 @@
+use std::{
+    collections::VecDeque,
+    fs,
+    ops::{Index, IndexMut},
+    str::FromStr,
+};
+
+// A rectangular grid stored row by row, indexed by (x, y) from the top left
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+const DIRECTIONS: [(isize, isize); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, cells: Vec<T>) -> Self {
+        assert!(
+            width > 0 && cells.len().is_multiple_of(width),
+            "Grid cells don't fill whole rows"
+        );
+
+        Grid {
+            width,
+            height: cells.len() / width,
+            cells,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: isize, y: isize) -> Option<&T> {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return None;
+        }
+
+        Some(&self.cells[y as usize * self.width + x as usize])
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.height).flat_map(|y| (0..self.width).map(move |x| (x, y)))
+    }
+
+    // The cells directly above, right of, below and left of a cell that are on the grid
+    pub fn neighbours(&self, (x, y): (usize, usize)) -> impl Iterator<Item = (usize, usize)> + '_ {
+        DIRECTIONS.iter().filter_map(move |(dx, dy)| {
+            let (x, y) = (x as isize + dx, y as isize + dy);
+            self.get(x, y).map(|_| (x as usize, y as usize))
+        })
+    }
+
+    pub fn map<U>(&self, f: impl Fn(&T) -> U) -> Grid<U> {
+        Grid::new(self.width, self.cells.iter().map(f).collect())
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        assert!(x < self.width, "x {x} is off the grid");
+        &self.cells[y * self.width + x]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+        assert!(x < self.width, "x {x} is off the grid");
+        &mut self.cells[y * self.width + x]
+    }
+}
+
+impl FromStr for Grid<char> {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let width = s.lines().next().map_or(0, |line| line.chars().count());
+        if width == 0 {
+            return Err("Grid is empty".to_string());
+        }
+
+        let mut cells = vec![];
+        for (y, line) in s.lines().enumerate() {
+            if line.chars().count() != width {
+                return Err(format!("Row {y} isn't {width} cells wide"));
+            }
+            cells.extend(line.chars());
+        }
+
+        Ok(Grid::new(width, cells))
+    }
+}
+
+impl Grid<char> {
+    pub fn from_file(file: &str) -> Self {
+        fs::read_to_string(file)
+            .expect("Error reading file")
+            .parse()
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+}
+
+// A group of orthogonally connected cells that share a label
+#[derive(Clone, Debug, PartialEq)]
+pub struct Region<T> {
+    pub label: T,
+    pub cells: Vec<(usize, usize)>,
+    pub area: usize,
+    // Cell edges that border another region or the outside of the grid
+    pub perimeter: usize,
+    // Straight runs of fence, however long
+    pub sides: usize,
+    pub corners: usize,
+    // Pockets of other cells enclosed by the region. Pockets that touch only at a corner
+    // count separately, the same way their fences do
+    pub holes: usize,
+}
+
+struct UnionFind {
+    parents: Vec<usize>,
+    sizes: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(size: usize) -> Self {
+        UnionFind {
+            parents: (0..size).collect(),
+            sizes: vec![1; size],
+        }
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parents[i] != i {
+            self.parents[i] = self.parents[self.parents[i]];
+            i = self.parents[i];
+        }
+        i
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return;
+        }
+
+        if self.sizes[a] < self.sizes[b] {
+            std::mem::swap(&mut a, &mut b);
+        }
+        self.parents[b] = a;
+        self.sizes[a] += self.sizes[b];
+    }
+}
+
+impl<T: Clone + PartialEq> Grid<T> {
+    // Labels every region, in reading order of each region's first cell
+    pub fn regions(&self) -> Vec<Region<T>> {
+        let mut union_find = UnionFind::new(self.cells.len());
+
+        for (x, y) in self.positions() {
+            let cell = y * self.width + x;
+            if x + 1 < self.width && self.cells[cell] == self.cells[cell + 1] {
+                union_find.union(cell, cell + 1);
+            }
+            if y + 1 < self.height && self.cells[cell] == self.cells[cell + self.width] {
+                union_find.union(cell, cell + self.width);
+            }
+        }
+
+        let roots: Vec<usize> = (0..self.cells.len())
+            .map(|cell| union_find.find(cell))
+            .collect();
+        let roots = Grid::new(self.width, roots);
+
+        let mut cells_by_root: Vec<Vec<(usize, usize)>> = vec![vec![]; self.cells.len()];
+        let mut order = vec![];
+        for position in self.positions() {
+            let root = roots[position];
+            if cells_by_root[root].is_empty() {
+                order.push(root);
+            }
+            cells_by_root[root].push(position);
+        }
+
+        order
+            .into_iter()
+            .map(|root| {
+                let cells = std::mem::take(&mut cells_by_root[root]);
+                let inside = |x: isize, y: isize| roots.get(x, y) == Some(&root);
+
+                let mut region = Region {
+                    label: self[cells[0]].clone(),
+                    area: cells.len(),
+                    perimeter: 0,
+                    sides: 0,
+                    corners: 0,
+                    holes: count_holes(&cells, inside),
+                    cells,
+                };
+
+                for &(x, y) in &region.cells {
+                    let (x, y) = (x as isize, y as isize);
+
+                    for (i, &(dx, dy)) in DIRECTIONS.iter().enumerate() {
+                        let (px, py) = DIRECTIONS[(i + 3) % 4];
+                        let (nx, ny) = DIRECTIONS[(i + 1) % 4];
+
+                        if !inside(x + dx, y + dy) {
+                            region.perimeter += 1;
+
+                            // Only the first cell along each run of fence starts a new side
+                            if !inside(x + px, y + py) || inside(x + px + dx, y + py + dy) {
+                                region.sides += 1;
+                            }
+                        }
+
+                        // Outside corners have both neighbours outside, and inside corners
+                        // have both neighbours inside but the diagonal outside
+                        let (a, b) = (inside(x + dx, y + dy), inside(x + nx, y + ny));
+                        if (!a && !b) || (a && b && !inside(x + dx + nx, y + dy + ny)) {
+                            region.corners += 1;
+                        }
+                    }
+                }
+
+                region
+            })
+            .collect()
+    }
+}
+
+// Floods the cells around the region within its bounding box, plus a margin so the
+// outside is a single pocket. Every other pocket is a hole
+fn count_holes(cells: &[(usize, usize)], inside: impl Fn(isize, isize) -> bool) -> usize {
+    let min_x = cells.iter().map(|(x, _)| *x as isize).min().unwrap() - 1;
+    let max_x = cells.iter().map(|(x, _)| *x as isize).max().unwrap() + 1;
+    let min_y = cells.iter().map(|(_, y)| *y as isize).min().unwrap() - 1;
+    let max_y = cells.iter().map(|(_, y)| *y as isize).max().unwrap() + 1;
+
+    let width = (max_x - min_x + 1) as usize;
+    let height = (max_y - min_y + 1) as usize;
+    let mut seen = vec![false; width * height];
+    let index = |x: isize, y: isize| (y - min_y) as usize * width + (x - min_x) as usize;
+
+    let mut pockets = 0;
+    for y in min_y..=max_y {
+        for x in min_x..=max_x {
+            if seen[index(x, y)] || inside(x, y) {
+                continue;
+            }
+
+            pockets += 1;
+            seen[index(x, y)] = true;
+            let mut queue = VecDeque::from([(x, y)]);
+
+            while let Some((x, y)) = queue.pop_front() {
+                for (dx, dy) in DIRECTIONS {
+                    let (x, y) = (x + dx, y + dy);
+                    if x < min_x || x > max_x || y < min_y || y > max_y {
+                        continue;
+                    }
+                    if !seen[index(x, y)] && !inside(x, y) {
+                        seen[index(x, y)] = true;
+                        queue.push_back((x, y));
+                    }
+                }
+            }
+        }
+    }
+
+    pockets - 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let grid: Grid<char> = "ab\ncd".parse().unwrap();
+
+        assert_eq!((grid.width(), grid.height()), (2, 2));
+        assert_eq!(grid[(1, 0)], 'b');
+        assert_eq!(grid.get(-1, 0), None);
+        assert_eq!(
+            grid.neighbours((0, 0)).collect::<Vec<_>>(),
+            [(1, 0), (0, 1)]
+        );
+        assert!("ab\nc".parse::<Grid<char>>().is_err());
+    }
+
+    #[test]
+    fn test_regions() {
+        let grid: Grid<char> = "AAAA\nBBCD\nBBCC\nEEEC".parse().unwrap();
+        let regions = grid.regions();
+
+        let summary: Vec<(char, usize, usize, usize)> = regions
+            .iter()
+            .map(|region| (region.label, region.area, region.perimeter, region.sides))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ('A', 4, 10, 4),
+                ('B', 4, 8, 4),
+                ('C', 4, 10, 8),
+                ('D', 1, 4, 4),
+                ('E', 3, 8, 4)
+            ]
+        );
+        assert_eq!(regions[2].cells, [(2, 1), (2, 2), (3, 2), (3, 3)]);
+    }
+
+    #[test]
+    fn test_holes() {
+        // Two pockets that only touch diagonally, and a separate X region inside each
+        let grid: Grid<char> = "AAAAAA\nAAABBA\nAAABBA\nABBAAA\nABBAAA\nAAAAAA"
+            .parse()
+            .unwrap();
+        let regions = grid.regions();
+
+        assert_eq!(regions[0].holes, 2);
+        assert_eq!(regions[0].sides, 12);
+        assert_eq!(regions[0].corners, 12);
+        assert_eq!(regions[1].holes, 0);
+
+        let grid: Grid<char> = "OOOOO\nOXOXO\nOOOOO\nOXOXO\nOOOOO".parse().unwrap();
+        let regions = grid.regions();
+        assert_eq!((regions[0].holes, regions[0].perimeter), (4, 36));
+    }
+}
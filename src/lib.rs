@@ -4,7 +4,7 @@ extern crate test;
 pub use test::Bencher;
 
 pub type ProblemDefinition = (
-    &'static dyn Fn() -> ProblemAnswer,
+    &'static (dyn Fn() -> ProblemAnswer + Sync),
     ProblemAnswer,
     &'static str,
 );
@@ -15,6 +15,16 @@ pub enum ProblemAnswer {
     String(String),
 }
 
+impl std::fmt::Display for ProblemAnswer {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ProblemAnswer::Signed(value) => write!(f, "{value}"),
+            ProblemAnswer::Unsigned(value) => write!(f, "{value}"),
+            ProblemAnswer::String(value) => write!(f, "{value}"),
+        }
+    }
+}
+
 #[macro_export()]
 macro_rules! define_problem {
     ($year:ident::$day:ident::$task:ident, $expected:expr) => {{
@@ -22,7 +32,7 @@ macro_rules! define_problem {
         use $year::$day::$task::ans as ans_func;
         let expected_value = $expected;
         if expected_value < 0 {
-            let return_function: &dyn Fn() -> ProblemAnswer =
+            let return_function: &(dyn Fn() -> ProblemAnswer + Sync) =
                 &|| ProblemAnswer::Signed(ans_func() as i128);
             (
                 return_function,
@@ -30,7 +40,7 @@ macro_rules! define_problem {
                 problem_number,
             )
         } else {
-            let return_function: &dyn Fn() -> ProblemAnswer =
+            let return_function: &(dyn Fn() -> ProblemAnswer + Sync) =
                 &|| ProblemAnswer::Unsigned(ans_func() as u128);
             (
                 return_function,
@@ -47,7 +57,7 @@ macro_rules! define_string_problem {
         let problem_number = &stringify!($year::$day::$task)[..];
         use $year::$day::$task::ans as ans_func;
         let expected_value = $expected;
-        let return_function: &dyn Fn() -> ProblemAnswer =
+        let return_function: &(dyn Fn() -> ProblemAnswer + Sync) =
             &|| ProblemAnswer::String(ans_func() as String);
         (
             return_function,
@@ -57,6 +67,12 @@ macro_rules! define_string_problem {
     }};
 }
 
+pub mod examples;
+pub mod grid;
+pub mod profiling;
+pub mod submit;
+#[cfg(target_os = "linux")]
+pub mod watch;
 pub mod year2022;
 pub mod year2023;
 pub mod year2024;
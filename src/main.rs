@@ -1,9 +1,332 @@
-use std::time::Instant;
+#[cfg(target_os = "linux")]
+use std::process::Command;
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU8, AtomicUsize, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
 
 use advent_of_code::*;
 
 fn main() {
-    let _problems_2022: Vec<ProblemDefinition> = vec![
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(|arg| arg.as_str()).collect();
+    let (args, options) = parse_run_options(&args);
+
+    match args.as_slice() {
+        [] => run_year_problems("2024", problems_2024(), &options),
+        ["run", year] => run_year_problems(year, year_problems(year), &options),
+        ["submit", year, day, part] => submit(year, day, part),
+        ["examples", year, day, page, rest @ ..] => extract_examples(year, day, page, rest),
+        ["day", year, day] => run_day(year, day, &options),
+        #[cfg(target_os = "linux")]
+        ["watch", year, day] => watch_day(year, day, &options),
+        #[cfg(not(target_os = "linux"))]
+        ["watch", _, _] => {
+            eprintln!("watch is only supported on Linux");
+            std::process::exit(1);
+        }
+        ["trace", "2024", "17", rest @ ..] => year2024::day17::trace_program(
+            rest.first().unwrap_or(&"resources/2024/day17/input.txt"),
+            std::io::stdout(),
+        ),
+        ["debug", "2024", "17", rest @ ..] => year2024::day17::debug(
+            rest.first().unwrap_or(&"resources/2024/day17/input.txt"),
+            std::io::stdin().lock(),
+            std::io::stdout(),
+        ),
+        ["debug", "2024", "15", "--wide", rest @ ..] => debug_warehouse(true, rest),
+        ["debug", "2024", "15", rest @ ..] => debug_warehouse(false, rest),
+        ["trace", "2022", "11", rest @ ..] => trace_keep_away(rest),
+        ["trace", "2022", "5", rest @ ..] => trace_crates(rest),
+        ["tree", "2022", "7", rest @ ..] => year2022::day07::explore_filesystem(
+            rest.get(1).unwrap_or(&"resources/2022/day07/input"),
+            rest.first().unwrap_or(&"/"),
+            std::io::stdout(),
+        ),
+        ["explain", "2024", "7", rest @ ..] => explain_equations(rest),
+        ["analyse", "2024", "5", rest @ ..] => year2024::day05::check_updates(
+            rest.first().unwrap_or(&"resources/2024/day05/input.txt"),
+            std::io::stdout(),
+        ),
+        ["analyse", "2023", "19", rest @ ..] => year2023::day19::analyse_workflows(
+            rest.first().unwrap_or(&"resources/2023/day19/input"),
+            std::io::stdout(),
+        ),
+        ["viz", "2023", "20", "--dot", rest @ ..] => visualise_modules(rest),
+        ["viz", "2024", "14", rest @ ..] => render_robots(rest),
+        ["viz", "2024", "16", rest @ ..] => explore_routes(rest),
+        ["viz", "2024", "6", rest @ ..] => year2024::day06::render_loops(
+            rest.first().unwrap_or(&"resources/2024/day06/input.txt"),
+            std::io::stdout(),
+        ),
+        _ => {
+            eprintln!("{USAGE}");
+            std::process::exit(1);
+        }
+    }
+}
+
+const USAGE: &str = "Usage: advent-of-code [command] [--jobs <n>] [--timeout <secs>]
+    run <year>                 Check every problem of the year
+    day <year> <day>           Check both tasks of a day
+    watch <year> <day>         Rerun a day whenever its source or resources change (Linux)
+    submit <year> <day> <part> Submit the computed answer
+    trace 2024 17 [input]      Print every step of the 3-bit computer
+    trace 2022 11 [--rounds <n>] [--modulus | --no-relief] [--big] [input]
+                               Print the monkeys' items and inspections after each round
+    trace 2022 5 [--9001] [input]
+                               Draw the crate stacks after every move
+    tree 2022 7 [dir] [input]  Draw a directory from the terminal log with its size, and
+                               the smallest directory to delete for the update
+    debug 2024 17 [input]      Step through the 3-bit computer with breakpoints
+    debug 2024 15 [--wide] [input]
+                               Step the warehouse robot back and forth through its moves
+    explain 2024 7 [--operators <list>] [input]
+                               Show how each calibration equation can be made true, using
+                               operators from a list such as \"+ * || - ^ ||2\"
+    analyse 2023 19 [input]    Show each part's workflow path and any unreachable rules
+    analyse 2024 5 [input]     List the rules each update breaks and how to reorder it
+    viz 2023 20 --dot [--presses <n>] [input]
+                               Print the module network as graphviz DOT, with edges
+                               coloured by the last pulse after n presses
+    viz 2024 6 [input]         Mark every new obstacle position that traps the guard
+    viz 2024 14 [--arena <w>x<h>] [--step <n>] [--pbm] [input]
+                               Draw the robots after n seconds, or when the tree appears
+    viz 2024 16 [--costs <step>,<turn>] [--routes <k>] [input]
+                               Draw a lowest scoring route through the maze, count the
+                               routes that tie with it, and list the k best routes
+    examples <year> <day> <page.html> [--main <block>] [--force]
+                               Extract examples from a saved puzzle page, overwriting
+                               existing example files only when forced";
+
+fn trace_keep_away(args: &[&str]) {
+    let mut rounds = 20;
+    let mut relief = year2022::day11::Relief::DivideBy(3);
+    let mut big_integers = false;
+    let mut file = "resources/2022/day11/input";
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match *arg {
+            "--rounds" => {
+                rounds = args
+                    .next()
+                    .and_then(|rounds| rounds.parse().ok())
+                    .expect("Rounds must be a number")
+            }
+            "--modulus" => relief = year2022::day11::Relief::Modulus,
+            "--no-relief" => relief = year2022::day11::Relief::None,
+            "--big" => big_integers = true,
+            path => file = path,
+        }
+    }
+
+    year2022::day11::trace_keep_away(file, rounds, relief, big_integers, std::io::stdout());
+}
+
+fn trace_crates(args: &[&str]) {
+    let (crane, rest) = match args {
+        ["--9001", rest @ ..] => (year2022::day05::CrateMover::Model9001, rest),
+        rest => (year2022::day05::CrateMover::Model9000, rest),
+    };
+
+    year2022::day05::trace_crates(
+        rest.first().unwrap_or(&"resources/2022/day05/input"),
+        crane,
+        std::io::stdout(),
+    );
+}
+
+fn explain_equations(args: &[&str]) {
+    let (operators, rest) = match args {
+        ["--operators", operators, rest @ ..] => (*operators, rest),
+        rest => ("+ * ||", rest),
+    };
+    let operators: Vec<year2024::day07::Operator> = operators
+        .split_whitespace()
+        .map(|operator| operator.parse().unwrap_or_else(|error| panic!("{error}")))
+        .collect();
+
+    year2024::day07::explain_equations(
+        rest.first().unwrap_or(&"resources/2024/day07/input.txt"),
+        &operators,
+        std::io::stdout(),
+    );
+}
+
+fn render_robots(args: &[&str]) {
+    let mut arena = None;
+    let mut step = None;
+    let mut format = year2024::day14::ImageFormat::Text;
+    let mut file = "resources/2024/day14/input.txt";
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match *arg {
+            "--arena" => {
+                arena = Some(
+                    args.next()
+                        .expect("Missing arena size")
+                        .parse()
+                        .unwrap_or_else(|error| panic!("{error}")),
+                )
+            }
+            "--step" => {
+                step = Some(
+                    args.next()
+                        .and_then(|step| step.parse().ok())
+                        .expect("Step must be a number"),
+                )
+            }
+            "--pbm" => format = year2024::day14::ImageFormat::Pbm,
+            path => file = path,
+        }
+    }
+
+    year2024::day14::render_robots(file, arena, step, format, std::io::stdout());
+}
+
+fn explore_routes(args: &[&str]) {
+    let mut costs = year2024::day16::PUZZLE_COSTS;
+    let mut routes = 5;
+    let mut file = "resources/2024/day16/input.txt";
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match *arg {
+            "--costs" => {
+                costs = args
+                    .next()
+                    .expect("Missing costs")
+                    .parse()
+                    .unwrap_or_else(|error| panic!("{error}"))
+            }
+            "--routes" => {
+                routes = args
+                    .next()
+                    .and_then(|routes| routes.parse().ok())
+                    .expect("Routes must be a number")
+            }
+            path => file = path,
+        }
+    }
+
+    year2024::day16::explore_routes(file, costs, routes, std::io::stdout());
+}
+
+fn debug_warehouse(wide: bool, args: &[&str]) {
+    year2024::day15::debug_warehouse(
+        args.first().unwrap_or(&"resources/2024/day15/input.txt"),
+        wide,
+        std::io::stdin().lock(),
+        std::io::stdout(),
+    );
+}
+
+fn visualise_modules(args: &[&str]) {
+    let (presses, rest) = match args {
+        ["--presses", presses, rest @ ..] => {
+            (Some(presses.parse().expect("Presses must be a number")), rest)
+        }
+        rest => (None, rest),
+    };
+
+    year2023::day20::write_dot(
+        rest.first().unwrap_or(&"resources/2023/day20/input"),
+        presses,
+        std::io::stdout(),
+    );
+}
+
+fn year_problems(year: &str) -> Vec<ProblemDefinition> {
+    match year {
+        "2022" => problems_2022(),
+        "2023" => problems_2023(),
+        "2024" => problems_2024(),
+        _ => panic!("No problems defined for {year}"),
+    }
+}
+
+fn find_problem(year: &str, day: &str, part: &str) -> ProblemDefinition {
+    let day: u8 = day.parse().expect("Day must be a number");
+    let name = format!("year{year}::day{day:02}::task{part}");
+
+    year_problems(year)
+        .into_iter()
+        .find(|(_, _, problem_name)| problem_name.replace(' ', "") == name)
+        .unwrap_or_else(|| panic!("No problem registered for {name}"))
+}
+
+fn submit(year: &str, day: &str, part: &str) {
+    let (problem_function, _, problem_name) = find_problem(year, day, part);
+
+    let year: u16 = year.parse().expect("Year must be a number");
+    let day: u8 = day.parse().expect("Day must be a number");
+    let part: u8 = part.parse().expect("Part must be a number");
+
+    let answer = problem_function().to_string();
+    println!("Problem {problem_name} answered {answer}");
+
+    let client = submit::Client::from_env().unwrap_or_else(|error| panic!("{error}"));
+    let mut history = submit::History::for_day(year, day);
+
+    match submit::submit_answer(&client, &mut history, (year, day, part), &answer) {
+        Ok(outcome) => println!("{outcome}"),
+        Err(reason) => {
+            eprintln!("Not submitting: {reason}");
+            std::process::exit(1);
+        }
+    }
+}
+
+fn extract_examples(year: &str, day: &str, page: &str, args: &[&str]) {
+    let day: u8 = day.parse().expect("Day must be a number");
+    let mut main_block = 1;
+    let mut force = false;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match *arg {
+            "--main" => {
+                main_block = args
+                    .next()
+                    .and_then(|main_block| main_block.parse().ok())
+                    .expect("Main block must be a number")
+            }
+            "--force" => force = true,
+            _ => {
+                eprintln!("{USAGE}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let html = std::fs::read_to_string(page).expect("Error reading puzzle page");
+    let extracted = examples::extract(&html);
+
+    for (index, block) in extracted.blocks.iter().enumerate() {
+        println!("Block {}:\n{block}", index + 1);
+    }
+    println!("Emphasised answers: {}", extracted.answers.join(", "));
+
+    let dir = format!("resources/{year}/day{day:02}");
+    match examples::write_examples(std::path::Path::new(&dir), &extracted, main_block, force) {
+        Ok(()) => println!("Wrote examples to {dir}"),
+        Err(error) => {
+            eprintln!("{error}");
+            std::process::exit(1);
+        }
+    }
+}
+
+fn problems_2022() -> Vec<ProblemDefinition> {
+    vec![
         define_problem!(year2022::day01::task1, 74711),
         define_problem!(year2022::day01::task2, 209481),
         define_problem!(year2022::day02::task1, 13682),
@@ -34,12 +357,16 @@ fn main() {
         define_problem!(year2022::day14::task2, 27566),
         define_problem!(year2022::day15::task1, 5716881),
         define_problem!(year2022::day15::task2, 10852583132904u128),
-        // define_problem!(year2022::day16::task1, 1707),
-        // define_problem!(year2022::day16::task2, 0),
+        define_problem!(year2022::day16::task1, 1796),
+        define_problem!(year2022::day16::task2, 1999),
         define_problem!(year2022::day18::task1, 4310),
         define_problem!(year2022::day18::task2, 2466),
-    ];
-    let _problems_2023: Vec<ProblemDefinition> = vec![
+        // Days 17 and 19 to 25 stay out of the list until their inputs are downloaded
+    ]
+}
+
+fn problems_2023() -> Vec<ProblemDefinition> {
+    vec![
         define_problem!(year2023::day01::task1, 56506),
         define_problem!(year2023::day01::task2, 56017),
         define_problem!(year2023::day02::task1, 2237),
@@ -90,9 +417,11 @@ fn main() {
         define_problem!(year2023::day24::task2, 885093461440405u128),
         define_problem!(year2023::day25::task1, 538560),
         define_problem!(year2023::day25::task2, 0),
-    ];
+    ]
+}
 
-    let problems_2024: Vec<ProblemDefinition> = vec![
+fn problems_2024() -> Vec<ProblemDefinition> {
+    vec![
         define_problem!(year2024::day01::task1, 2378066),
         define_problem!(year2024::day01::task2, 18934359),
         define_problem!(year2024::day02::task1, 220),
@@ -126,53 +455,388 @@ fn main() {
         define_problem!(year2024::day16::task1, 98484),
         define_problem!(year2024::day16::task2, 531),
         define_string_problem!(year2024::day17::task1, "3,1,5,3,7,4,2,7,5"),
-        define_problem!(year2024::day17::task2, 0),
-    ];
+        define_problem!(year2024::day17::task2, 190593310997519u128),
+        // Days 18 to 25 stay out of the list until their inputs are downloaded
+    ]
+}
+
+struct RunOptions {
+    jobs: usize,
+    timeout: Option<Duration>,
+}
+
+struct Measurement {
+    answer: String,
+    duration: f32,
+    allocations: Option<profiling::AllocStats>,
+    // Timed out problems were still running alongside it, so the timing is unreliable
+    overlapped: bool,
+}
+
+enum ProblemStatus {
+    Passed(Measurement),
+    Failed(Measurement, String),
+    // Threads can't be killed, so the problem keeps running in the background until it
+    // finishes or the process exits
+    TimedOut,
+    Panicked,
+}
+
+// Timed out problems whose threads are still running
+static ABANDONED_PROBLEMS: AtomicUsize = AtomicUsize::new(0);
+
+const RUNNING: u8 = 0;
+const FINISHED: u8 = 1;
+const ABANDONED: u8 = 2;
+
+// Marks a problem's thread as finished when dropped, even if the problem panics, and
+// stops counting it as abandoned if it had already timed out
+struct ProblemThread(Arc<AtomicU8>);
+
+impl Drop for ProblemThread {
+    fn drop(&mut self) {
+        if self.0.swap(FINISHED, Ordering::SeqCst) == ABANDONED {
+            ABANDONED_PROBLEMS.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+impl ProblemStatus {
+    fn measurement(&self) -> Option<&Measurement> {
+        match self {
+            ProblemStatus::Passed(measurement) | ProblemStatus::Failed(measurement, _) => {
+                Some(measurement)
+            }
+            _ => None,
+        }
+    }
+}
+
+fn parse_run_options<'a>(args: &[&'a str]) -> (Vec<&'a str>, RunOptions) {
+    let mut options = RunOptions {
+        jobs: 1,
+        timeout: None,
+    };
+    let mut positional = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match *arg {
+            "--jobs" => {
+                options.jobs = args
+                    .next()
+                    .and_then(|jobs| jobs.parse().ok())
+                    .filter(|jobs| *jobs > 0)
+                    .expect("--jobs takes a positive number");
+            }
+            "--timeout" => {
+                let seconds: f32 = args
+                    .next()
+                    .and_then(|seconds| seconds.parse().ok())
+                    .expect("--timeout takes a number of seconds");
+                options.timeout = Some(Duration::from_secs_f32(seconds));
+            }
+            _ => positional.push(*arg),
+        }
+    }
+
+    (positional, options)
+}
 
-    run_year_problems("2024", problems_2024);
+fn run_day(year: &str, day: &str, options: &RunOptions) {
+    let day: u8 = day.parse().expect("Day must be a number");
+    let prefix = format!("year{year}::day{day:02}::");
+
+    let problems: Vec<ProblemDefinition> = year_problems(year)
+        .into_iter()
+        .filter(|(_, _, problem_name)| problem_name.replace(' ', "").starts_with(&prefix))
+        .collect();
+
+    let statuses = run_problems(&problems, options);
+
+    // One "<problem> = <answer>" line per task, which watch mode compares between runs
+    println!();
+    for ((_, _, problem_name), status) in problems.iter().zip(statuses) {
+        let answer = match status {
+            ProblemStatus::Passed(measurement) => format!("{} (correct)", measurement.answer),
+            ProblemStatus::Failed(measurement, reason) => {
+                format!("{} ({reason})", measurement.answer)
+            }
+            ProblemStatus::TimedOut => "timed out".to_string(),
+            ProblemStatus::Panicked => "panicked".to_string(),
+        };
+        println!("{} = {answer}", problem_name.replace(' ', ""));
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn watch_day(year: &str, day: &str, options: &RunOptions) {
+    let day: u8 = day.parse().expect("Day must be a number");
+
+    let mut watcher =
+        watch::DayWatcher::new(&watch::day_paths(year, day)).expect("Error watching day");
+    let mut previous = BTreeMap::new();
+
+    loop {
+        if let Some(answers) = rerun_day(year, day, options) {
+            println!("\nAnswers compared to the previous run:");
+            for line in watch::diff_answers(&previous, &answers) {
+                println!("{line}");
+            }
+            previous = answers;
+        }
+
+        println!("\nWatching year{year}::day{day:02} for changes...");
+        let changed = watcher.wait_for_change().expect("Error watching day");
+        println!("\n\nChanged: {}", changed.join(", "));
+    }
 }
 
-fn run_year_problems(year: &str, problems: Vec<ProblemDefinition>) {
+#[cfg(target_os = "linux")]
+fn rerun_day(year: &str, day: u8, options: &RunOptions) -> Option<BTreeMap<String, String>> {
+    // The running binary is stale once the source changes, so everything goes through cargo
+    let cargo = || {
+        let mut command = Command::new("cargo");
+        command.arg("--quiet");
+        command
+    };
+
+    let built = cargo().args(["build", "--release"]).status().ok()?;
+    if !built.success() {
+        println!("Build failed");
+        return None;
+    }
+
+    println!("\nRunning examples...");
+    cargo()
+        .args(["test", "--release", "--lib"])
+        .arg(format!("year{year}::day{day:02}::"))
+        .status()
+        .ok()?;
+
+    println!("\nRunning input...");
+    let mut run = cargo();
+    run.args(["run", "--release", "--", "day", year, &day.to_string()]);
+    if let Some(timeout) = options.timeout {
+        run.args(["--timeout", &timeout.as_secs_f32().to_string()]);
+    }
+    let output = run.output().ok()?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    print!("{stdout}");
+    print!("{}", String::from_utf8_lossy(&output.stderr));
+
+    Some(watch::parse_answers(&stdout))
+}
+
+fn run_year_problems(year: &str, problems: Vec<ProblemDefinition>, options: &RunOptions) {
     println!("\n\nRunning problems for {year}...\n");
 
     let now = Instant::now();
 
-    let mut durations: Vec<(usize, f32)> = problems.iter().map(check_problem).enumerate().collect();
+    let statuses = run_problems(&problems, options);
 
     let duration = now.elapsed().as_secs_f32();
 
     println!("\n\nProblems for {year} took {duration} seconds");
 
+    let mut durations: Vec<(usize, f32)> = statuses
+        .iter()
+        .enumerate()
+        .filter_map(|(index, status)| Some((index, status.measurement()?)))
+        .filter(|(_, measurement)| !measurement.overlapped)
+        .map(|(index, measurement)| (index, measurement.duration))
+        .collect();
+
     durations.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
     println!("\n\nThe 5 slowest problems of {year} are:");
+    let overlapped = statuses
+        .iter()
+        .any(|status| status.measurement().is_some_and(|m| m.overlapped));
+    if overlapped {
+        println!("(leaving out problems timed while timed out problems were still running)");
+    }
     for (index, duration) in durations.into_iter().take(5) {
         let problem_name = problems[index].2;
         println!("Problem {} took {} seconds", problem_name, duration);
     }
+
+    let mut peaks: Vec<(usize, profiling::AllocStats)> = statuses
+        .iter()
+        .enumerate()
+        .filter_map(|(index, status)| Some((index, status.measurement()?.allocations?)))
+        .collect();
+
+    if !peaks.is_empty() {
+        peaks.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.peak_bytes));
+        println!("\n\nThe 5 problems of {year} with the highest peak heap usage are:");
+        for (index, stats) in peaks.into_iter().take(5) {
+            let problem_name = problems[index].2;
+            println!("Problem {problem_name} {}", format_alloc_stats(&stats));
+        }
+    }
+
+    let unsuccessful = statuses
+        .iter()
+        .filter(|status| !matches!(status, ProblemStatus::Passed(_)))
+        .count();
+    if unsuccessful > 0 {
+        println!("\n\n{unsuccessful} problems of {year} did not pass");
+        std::process::exit(1);
+    }
 }
 
-fn check_problem(problem: &ProblemDefinition) -> f32 {
-    let (problem_function, expected, problem_name) = problem;
+fn run_problems(problems: &[ProblemDefinition], options: &RunOptions) -> Vec<ProblemStatus> {
+    // Workers take the next unclaimed problem, while this thread prints results
+    // strictly in problem order so the output is the same for any number of jobs
+    let next_problem = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
 
-    let now = Instant::now();
+    if cfg!(feature = "alloc-profile") && (options.jobs > 1 || options.timeout.is_some()) {
+        println!("Heap stats are skipped for problems that ran alongside other problems\n");
+    }
 
-    let actual_result = problem_function();
+    thread::scope(|scope| {
+        for _ in 0..options.jobs.min(problems.len()) {
+            let sender = sender.clone();
+            let next_problem = &next_problem;
+            scope.spawn(move || loop {
+                let index = next_problem.fetch_add(1, Ordering::SeqCst);
+                if index >= problems.len() {
+                    break;
+                }
 
-    let duration = now.elapsed().as_secs_f32();
-    println!("Problem {problem_name} took {duration} seconds");
+                let status = check_problem(&problems[index], options.timeout);
+                sender.send((index, status)).unwrap();
+            });
+        }
+        drop(sender);
 
-    match (actual_result, expected) {
-        (ProblemAnswer::Signed(actual), ProblemAnswer::Signed(expected)) => {
-            assert_eq!(actual, *expected);
+        let mut pending = BTreeMap::new();
+        let mut statuses = Vec::with_capacity(problems.len());
+        for (index, status) in receiver {
+            pending.insert(index, status);
+
+            while let Some(status) = pending.remove(&statuses.len()) {
+                report_problem(problems[statuses.len()].2, &status);
+                statuses.push(status);
+            }
         }
-        (ProblemAnswer::Unsigned(actual), ProblemAnswer::Unsigned(expected)) => {
-            assert_eq!(actual, *expected);
+
+        statuses
+    })
+}
+
+fn report_problem(problem_name: &str, status: &ProblemStatus) {
+    match status {
+        ProblemStatus::Passed(measurement) => {
+            println!(
+                "Problem {problem_name} took {} seconds{}",
+                measurement.duration,
+                overlap_note(measurement)
+            )
         }
-        (ProblemAnswer::String(actual), ProblemAnswer::String(expected)) => {
-            assert_eq!(actual, *expected);
+        ProblemStatus::Failed(measurement, reason) => {
+            println!(
+                "Problem {problem_name} took {} seconds{} but failed: {reason}",
+                measurement.duration,
+                overlap_note(measurement)
+            )
         }
-        _ => panic!("Expected return type does not match actual return type"),
+        ProblemStatus::TimedOut => {
+            println!("Problem {problem_name} timed out, and keeps running in the background")
+        }
+        ProblemStatus::Panicked => println!("Problem {problem_name} panicked"),
+    }
+
+    if let Some(stats) = status.measurement().and_then(|m| m.allocations) {
+        println!("    {}", format_alloc_stats(&stats));
     }
+}
+
+fn overlap_note(measurement: &Measurement) -> &'static str {
+    if measurement.overlapped {
+        " (while timed out problems were still running)"
+    } else {
+        ""
+    }
+}
+
+fn format_alloc_stats(stats: &profiling::AllocStats) -> String {
+    format!(
+        "peaked at {} heap bytes, with {} allocations totalling {} bytes",
+        stats.peak_bytes, stats.allocations, stats.bytes_allocated
+    )
+}
 
-    duration
+fn check_problem(problem: &ProblemDefinition, timeout: Option<Duration>) -> ProblemStatus {
+    let (problem_function, expected, _) = problem;
+    let problem_function = *problem_function;
+
+    // The problem runs on its own thread so a timed out problem can be abandoned.
+    // A panicking problem drops the sender without sending, which disconnects the channel
+    let (sender, receiver) = mpsc::channel();
+    let state = Arc::new(AtomicU8::new(RUNNING));
+    let thread_state = ProblemThread(Arc::clone(&state));
+    thread::spawn(move || {
+        let _thread_state = thread_state;
+        let overlapped_before = ABANDONED_PROBLEMS.load(Ordering::SeqCst) > 0;
+        let now = Instant::now();
+
+        let (actual_result, allocations) = profiling::measure(problem_function);
+
+        // Abandoned problems keep competing for the CPU, which skews this problem's timing
+        let overlapped = overlapped_before || ABANDONED_PROBLEMS.load(Ordering::SeqCst) > 0;
+        let measurement = Measurement {
+            answer: actual_result.to_string(),
+            duration: now.elapsed().as_secs_f32(),
+            allocations,
+            overlapped,
+        };
+        let _ = sender.send((actual_result, measurement));
+    });
+
+    let (actual_result, measurement) = match timeout {
+        Some(timeout) => match receiver.recv_timeout(timeout) {
+            Ok(result) => result,
+            Err(RecvTimeoutError::Timeout) => {
+                // Counted before marking, so a thread finishing in between never sees an
+                // abandoned state that hasn't been counted yet
+                ABANDONED_PROBLEMS.fetch_add(1, Ordering::SeqCst);
+                if state
+                    .compare_exchange(RUNNING, ABANDONED, Ordering::SeqCst, Ordering::SeqCst)
+                    .is_err()
+                {
+                    ABANDONED_PROBLEMS.fetch_sub(1, Ordering::SeqCst);
+                }
+                return ProblemStatus::TimedOut;
+            }
+            Err(RecvTimeoutError::Disconnected) => return ProblemStatus::Panicked,
+        },
+        None => match receiver.recv() {
+            Ok(result) => result,
+            Err(_) => return ProblemStatus::Panicked,
+        },
+    };
+
+    let matches = match (&actual_result, expected) {
+        (ProblemAnswer::Signed(actual), ProblemAnswer::Signed(expected)) => actual == expected,
+        (ProblemAnswer::Unsigned(actual), ProblemAnswer::Unsigned(expected)) => actual == expected,
+        (ProblemAnswer::String(actual), ProblemAnswer::String(expected)) => actual == expected,
+        _ => {
+            return ProblemStatus::Failed(
+                measurement,
+                "Expected return type does not match actual return type".to_string(),
+            )
+        }
+    };
+
+    if matches {
+        ProblemStatus::Passed(measurement)
+    } else {
+        ProblemStatus::Failed(
+            measurement,
+            format!("expected {expected}, got {actual_result}"),
+        )
+    }
 }
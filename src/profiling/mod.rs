@@ -0,0 +1,161 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AllocStats {
+    pub peak_bytes: usize,
+    pub allocations: usize,
+    pub bytes_allocated: usize,
+}
+
+// Runs the function, returning heap usage while it ran when built with the alloc-profile feature.
+// The counters are global, so nothing is returned if another measurement ran at the same time
+pub fn measure<T>(function: impl FnOnce() -> T) -> (T, Option<AllocStats>) {
+    #[cfg(feature = "alloc-profile")]
+    {
+        let measurement = counting::Measurement::start();
+        let baseline = counting::reset();
+        let result = function();
+        let stats = counting::stats(baseline);
+        (result, measurement.alone().then_some(stats))
+    }
+
+    #[cfg(not(feature = "alloc-profile"))]
+    {
+        (function(), None)
+    }
+}
+
+#[cfg(feature = "alloc-profile")]
+mod counting {
+    use std::{
+        alloc::{GlobalAlloc, Layout, System},
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use super::AllocStats;
+
+    static CURRENT_BYTES: AtomicUsize = AtomicUsize::new(0);
+    static PEAK_BYTES: AtomicUsize = AtomicUsize::new(0);
+    static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+    static BYTES_ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+    // Measurements still running, and how many have ever started
+    static ACTIVE_MEASUREMENTS: AtomicUsize = AtomicUsize::new(0);
+    static STARTED_MEASUREMENTS: AtomicUsize = AtomicUsize::new(0);
+
+    struct CountingAllocator;
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    fn record_allocation(size: usize) {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES_ALLOCATED.fetch_add(size, Ordering::Relaxed);
+        let current = CURRENT_BYTES.fetch_add(size, Ordering::Relaxed) + size;
+        PEAK_BYTES.fetch_max(current, Ordering::Relaxed);
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let pointer = System.alloc(layout);
+            if !pointer.is_null() {
+                record_allocation(layout.size());
+            }
+            pointer
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            let pointer = System.alloc_zeroed(layout);
+            if !pointer.is_null() {
+                record_allocation(layout.size());
+            }
+            pointer
+        }
+
+        unsafe fn dealloc(&self, pointer: *mut u8, layout: Layout) {
+            System.dealloc(pointer, layout);
+            CURRENT_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+        }
+
+        unsafe fn realloc(&self, pointer: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let new_pointer = System.realloc(pointer, layout, new_size);
+            if !new_pointer.is_null() {
+                // Counted as freeing the old block and allocating the new one
+                CURRENT_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+                record_allocation(new_size);
+            }
+            new_pointer
+        }
+    }
+
+    // Stays counted as active until dropped, so a measurement that panics or is abandoned
+    // still overlaps with everything started while it runs
+    pub struct Measurement {
+        started: usize,
+        others_running: bool,
+    }
+
+    impl Measurement {
+        pub fn start() -> Self {
+            let others_running = ACTIVE_MEASUREMENTS.fetch_add(1, Ordering::SeqCst) > 0;
+            let started = STARTED_MEASUREMENTS.fetch_add(1, Ordering::SeqCst) + 1;
+            Measurement {
+                started,
+                others_running,
+            }
+        }
+
+        // Whether no other measurement ran at any point since this one started
+        pub fn alone(&self) -> bool {
+            !self.others_running
+                && ACTIVE_MEASUREMENTS.load(Ordering::SeqCst) == 1
+                && STARTED_MEASUREMENTS.load(Ordering::SeqCst) == self.started
+        }
+    }
+
+    impl Drop for Measurement {
+        fn drop(&mut self) {
+            ACTIVE_MEASUREMENTS.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    // Returns the bytes in use at the time of the reset, which the peak is measured from
+    pub fn reset() -> usize {
+        let current = CURRENT_BYTES.load(Ordering::Relaxed);
+        PEAK_BYTES.store(current, Ordering::Relaxed);
+        ALLOCATIONS.store(0, Ordering::Relaxed);
+        BYTES_ALLOCATED.store(0, Ordering::Relaxed);
+        current
+    }
+
+    pub fn stats(baseline: usize) -> AllocStats {
+        AllocStats {
+            peak_bytes: PEAK_BYTES.load(Ordering::Relaxed).saturating_sub(baseline),
+            allocations: ALLOCATIONS.load(Ordering::Relaxed),
+            bytes_allocated: BYTES_ALLOCATED.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "alloc-profile"))]
+mod tests {
+    use super::measure;
+
+    #[test]
+    fn test_measure() {
+        let (length, stats) = measure(|| {
+            let first = vec![0u8; 1000];
+            let second = vec![0u8; 500];
+            first.len() + second.len()
+        });
+
+        let stats = stats.unwrap();
+        assert_eq!(length, 1500);
+        assert!(stats.allocations >= 2);
+        assert!(stats.bytes_allocated >= 1500);
+        assert!(stats.peak_bytes >= 1500);
+
+        // Kept in the same test, since tests running in parallel would overlap each other
+        let ((_, inner), outer) = measure(|| measure(|| vec![0u8; 1000]));
+        assert_eq!(inner, None);
+        assert_eq!(outer, None);
+    }
+}
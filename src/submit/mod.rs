@@ -0,0 +1,401 @@
+use std::{
+    fmt, fs,
+    io::Write,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+pub const DEFAULT_ENDPOINT: &str = "https://adventofcode.com";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Correct,
+    Incorrect,
+    TooHigh,
+    TooLow,
+    RateLimited(Duration),
+    WrongLevel,
+    Unknown,
+}
+
+impl Outcome {
+    fn tag(&self) -> String {
+        match self {
+            Outcome::Correct => "correct".to_string(),
+            Outcome::Incorrect => "incorrect".to_string(),
+            Outcome::TooHigh => "too_high".to_string(),
+            Outcome::TooLow => "too_low".to_string(),
+            Outcome::RateLimited(wait) => format!("rate_limited:{}", wait.as_secs()),
+            Outcome::WrongLevel => "wrong_level".to_string(),
+            Outcome::Unknown => "unknown".to_string(),
+        }
+    }
+
+    fn from_tag(tag: &str) -> Option<Self> {
+        let outcome = match tag.split_once(':') {
+            Some(("rate_limited", secs)) => {
+                Outcome::RateLimited(Duration::from_secs(secs.parse().ok()?))
+            }
+            _ => match tag {
+                "correct" => Outcome::Correct,
+                "incorrect" => Outcome::Incorrect,
+                "too_high" => Outcome::TooHigh,
+                "too_low" => Outcome::TooLow,
+                "wrong_level" => Outcome::WrongLevel,
+                "unknown" => Outcome::Unknown,
+                _ => return None,
+            },
+        };
+
+        Some(outcome)
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Outcome::Correct => write!(f, "That's the right answer"),
+            Outcome::Incorrect => write!(f, "That's not the right answer"),
+            Outcome::TooHigh => write!(f, "That's not the right answer, it's too high"),
+            Outcome::TooLow => write!(f, "That's not the right answer, it's too low"),
+            Outcome::RateLimited(wait) => {
+                write!(f, "Rate limited, try again in {} seconds", wait.as_secs())
+            }
+            Outcome::WrongLevel => write!(f, "Not the right level, is it already solved?"),
+            Outcome::Unknown => write!(f, "Unrecognised response"),
+        }
+    }
+}
+
+pub fn parse_response(body: &str) -> Outcome {
+    // The site answers with a full HTML page, the only part of interest is the
+    // text inside <article>, so matching on the phrases used there is enough
+    if body.contains("That's the right answer") {
+        Outcome::Correct
+    } else if body.contains("That's not the right answer") {
+        if body.contains("your answer is too high") {
+            Outcome::TooHigh
+        } else if body.contains("your answer is too low") {
+            Outcome::TooLow
+        } else {
+            Outcome::Incorrect
+        }
+    } else if body.contains("You gave an answer too recently") {
+        Outcome::RateLimited(parse_wait(body))
+    } else if body.contains("You don't seem to be solving the right level") {
+        Outcome::WrongLevel
+    } else {
+        Outcome::Unknown
+    }
+}
+
+fn parse_wait(body: &str) -> Duration {
+    // Eg. "You have 4m 32s left to wait." or "You have 32s left to wait."
+    let wait = body
+        .split("You have ")
+        .nth(1)
+        .and_then(|rest| rest.split(" left to wait").next())
+        .unwrap_or("");
+
+    let seconds = wait
+        .split_whitespace()
+        .map(|part| {
+            let (number, unit) = part.split_at(part.len() - 1);
+            let number: u64 = number.parse().unwrap_or(0);
+            match unit {
+                "h" => number * 3600,
+                "m" => number * 60,
+                _ => number,
+            }
+        })
+        .sum();
+
+    Duration::from_secs(seconds)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attempt {
+    pub timestamp: u64,
+    pub part: u8,
+    pub answer: String,
+    pub outcome: Outcome,
+}
+
+pub struct History {
+    path: PathBuf,
+    attempts: Vec<Attempt>,
+}
+
+impl History {
+    // Kept out of resources/ so submitting doesn't leave changes to commit
+    pub fn for_day(year: u16, day: u8) -> Self {
+        Self::from_file(format!(".aoc/submissions/{year}-{day:02}.txt"))
+    }
+
+    pub fn from_file(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let attempts = fs::read_to_string(&path)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split('\t');
+                Some(Attempt {
+                    timestamp: fields.next()?.parse().ok()?,
+                    part: fields.next()?.parse().ok()?,
+                    answer: fields.next()?.to_string(),
+                    outcome: Outcome::from_tag(fields.next()?)?,
+                })
+            })
+            .collect();
+
+        Self { path, attempts }
+    }
+
+    pub fn attempts(&self, part: u8) -> impl Iterator<Item = &Attempt> {
+        self.attempts
+            .iter()
+            .filter(move |attempt| attempt.part == part)
+    }
+
+    pub fn record(&mut self, part: u8, answer: &str, outcome: Outcome) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let attempt = Attempt {
+            timestamp,
+            part,
+            answer: answer.to_string(),
+            outcome,
+        };
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).unwrap();
+        }
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .unwrap();
+        writeln!(
+            file,
+            "{}\t{}\t{}\t{}",
+            attempt.timestamp,
+            attempt.part,
+            attempt.answer,
+            attempt.outcome.tag()
+        )
+        .unwrap();
+
+        self.attempts.push(attempt);
+    }
+
+    // Returns the reason an answer shouldn't be sent, if the history already rules it out
+    pub fn check(&self, part: u8, answer: &str) -> Result<(), String> {
+        let mut lowest_too_high: Option<i128> = None;
+        let mut highest_too_low: Option<i128> = None;
+
+        for attempt in self.attempts(part) {
+            match attempt.outcome {
+                Outcome::Correct => {
+                    return Err(format!(
+                        "Part {part} was already solved with {}",
+                        attempt.answer
+                    ))
+                }
+                Outcome::Incorrect | Outcome::TooHigh | Outcome::TooLow
+                    if attempt.answer == answer =>
+                {
+                    return Err(format!("{answer} is already known to be wrong"))
+                }
+                _ => {}
+            }
+
+            let Ok(value) = attempt.answer.parse::<i128>() else {
+                continue;
+            };
+            match attempt.outcome {
+                Outcome::TooHigh => {
+                    lowest_too_high = Some(lowest_too_high.map_or(value, |v| v.min(value)))
+                }
+                Outcome::TooLow => {
+                    highest_too_low = Some(highest_too_low.map_or(value, |v| v.max(value)))
+                }
+                _ => {}
+            }
+        }
+
+        if let Ok(value) = answer.parse::<i128>() {
+            if let Some(bound) = lowest_too_high.filter(|bound| value >= *bound) {
+                return Err(format!(
+                    "{answer} is not below known too high answer {bound}"
+                ));
+            }
+            if let Some(bound) = highest_too_low.filter(|bound| value <= *bound) {
+                return Err(format!(
+                    "{answer} is not above known too low answer {bound}"
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Client {
+    endpoint: String,
+    session: String,
+}
+
+impl Client {
+    pub fn new(endpoint: &str, session: &str) -> Self {
+        Self {
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            session: session.to_string(),
+        }
+    }
+
+    // Reads AOC_ENDPOINT and AOC_SESSION, so the client can be pointed at a local mock
+    pub fn from_env() -> Result<Self, String> {
+        let endpoint =
+            std::env::var("AOC_ENDPOINT").unwrap_or_else(|_| DEFAULT_ENDPOINT.to_string());
+        let session = std::env::var("AOC_SESSION")
+            .map_err(|_| "AOC_SESSION must be set to the session cookie".to_string())?;
+
+        Ok(Self::new(&endpoint, &session))
+    }
+
+    pub fn submit(&self, year: u16, day: u8, part: u8, answer: &str) -> Result<Outcome, String> {
+        let url = format!("{}/{year}/day/{day}/answer", self.endpoint);
+
+        let response = ureq::post(&url)
+            .set("Cookie", &format!("session={}", self.session))
+            .send_form(&[("level", &part.to_string()), ("answer", answer)])
+            .map_err(|error| error.to_string())?;
+
+        let body = response.into_string().map_err(|error| error.to_string())?;
+
+        Ok(parse_response(&body))
+    }
+}
+
+pub fn submit_answer(
+    client: &Client,
+    history: &mut History,
+    (year, day, part): (u16, u8, u8),
+    answer: &str,
+) -> Result<Outcome, String> {
+    history.check(part, answer)?;
+
+    let outcome = client.submit(year, day, part, answer)?;
+    history.record(part, answer, outcome.clone());
+
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{BufRead, BufReader, Read},
+        net::TcpListener,
+        thread,
+    };
+
+    fn temp_history(name: &str) -> History {
+        let path = std::env::temp_dir().join(format!("aoc_submissions_{name}.txt"));
+        let _ = fs::remove_file(&path);
+        History::from_file(path)
+    }
+
+    #[test]
+    fn test_parse_response() {
+        assert_eq!(
+            parse_response("<p>That's the right answer! You are one gold star closer</p>"),
+            Outcome::Correct
+        );
+        assert_eq!(
+            parse_response("<p>That's not the right answer; your answer is too high.</p>"),
+            Outcome::TooHigh
+        );
+        assert_eq!(
+            parse_response("<p>That's not the right answer.  If you're stuck...</p>"),
+            Outcome::Incorrect
+        );
+        assert_eq!(
+            parse_response("<p>You gave an answer too recently. You have 1m 5s left to wait.</p>"),
+            Outcome::RateLimited(Duration::from_secs(65))
+        );
+    }
+
+    #[test]
+    fn test_history_bounds() {
+        let mut history = temp_history("bounds");
+        history.record(1, "100", Outcome::TooHigh);
+        history.record(1, "20", Outcome::TooLow);
+        history.record(1, "50", Outcome::Incorrect);
+
+        assert!(history.check(1, "100").is_err());
+        assert!(history.check(1, "150").is_err());
+        assert!(history.check(1, "10").is_err());
+        assert!(history.check(1, "50").is_err());
+        assert!(history.check(1, "60").is_ok());
+        assert!(history.check(2, "150").is_ok());
+
+        let reloaded = History::from_file(&history.path);
+        assert_eq!(reloaded.attempts, history.attempts);
+    }
+
+    #[test]
+    fn test_submit_to_mock() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+
+            let mut content_length = 0;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                if header.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = header.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+
+            let page =
+                "<article><p>That's not the right answer; your answer is too low.</p></article>";
+            write!(
+                reader.get_mut(),
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{page}",
+                page.len()
+            )
+            .unwrap();
+
+            (request_line, String::from_utf8(body).unwrap())
+        });
+
+        let client = Client::new(&endpoint, "abc");
+        let mut history = temp_history("mock");
+
+        let outcome = submit_answer(&client, &mut history, (2024, 7, 2), "42").unwrap();
+        assert_eq!(outcome, Outcome::TooLow);
+
+        let (request_line, body) = server.join().unwrap();
+        assert!(request_line.starts_with("POST /2024/day/7/answer"));
+        assert_eq!(body, "level=2&answer=42");
+
+        assert!(submit_answer(&client, &mut history, (2024, 7, 2), "41").is_err());
+    }
+}
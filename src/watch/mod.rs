@@ -0,0 +1,181 @@
+use std::{
+    collections::BTreeMap,
+    io,
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+use inotify::{Inotify, WatchDescriptor, WatchMask};
+
+// Editors save by writing a temporary file and renaming it, so directories are
+// watched for anything ending up in them rather than the files themselves
+const CHANGE_MASK: WatchMask = WatchMask::CLOSE_WRITE
+    .union(WatchMask::MOVED_TO)
+    .union(WatchMask::CREATE)
+    .union(WatchMask::DELETE);
+
+const SETTLE_TIME: Duration = Duration::from_millis(200);
+
+pub fn day_paths(year: &str, day: u8) -> Vec<(PathBuf, Option<&'static str>)> {
+    vec![
+        (PathBuf::from(format!("src/year{year}/day{day:02}")), None),
+        (PathBuf::from(format!("resources/{year}/day{day:02}")), None),
+        (PathBuf::from("src"), Some("main.rs")),
+    ]
+}
+
+pub struct DayWatcher {
+    inotify: Inotify,
+    // A watch either reports every file in its directory, or only the named one
+    watches: Vec<(WatchDescriptor, Option<&'static str>)>,
+}
+
+impl DayWatcher {
+    pub fn new(paths: &[(PathBuf, Option<&'static str>)]) -> io::Result<Self> {
+        let inotify = Inotify::init()?;
+
+        let mut watches = Vec::new();
+        for (dir, only_file) in paths {
+            for dir in with_subdirectories(dir)? {
+                let descriptor = inotify.watches().add(&dir, CHANGE_MASK)?;
+                watches.push((descriptor, *only_file));
+            }
+        }
+
+        Ok(Self { inotify, watches })
+    }
+
+    // Blocks until a watched file changes, then waits for the burst of events
+    // from a save to finish and returns the names of every changed file
+    pub fn wait_for_change(&mut self) -> io::Result<Vec<String>> {
+        let mut buffer = [0; 4096];
+
+        loop {
+            let mut changed = self.relevant_names(&mut buffer, true)?;
+            if changed.is_empty() {
+                continue;
+            }
+
+            thread::sleep(SETTLE_TIME);
+            loop {
+                match self.relevant_names(&mut buffer, false) {
+                    Ok(names) if !names.is_empty() => changed.extend(names),
+                    Ok(_) => break,
+                    Err(error) if error.kind() == io::ErrorKind::WouldBlock => break,
+                    Err(error) => return Err(error),
+                }
+            }
+
+            changed.sort();
+            changed.dedup();
+            return Ok(changed);
+        }
+    }
+
+    fn relevant_names(&mut self, buffer: &mut [u8], blocking: bool) -> io::Result<Vec<String>> {
+        let events = if blocking {
+            self.inotify.read_events_blocking(buffer)?
+        } else {
+            self.inotify.read_events(buffer)?
+        };
+
+        let names = events
+            .filter_map(|event| {
+                let name = event.name?.to_string_lossy().to_string();
+                let only_file = self
+                    .watches
+                    .iter()
+                    .find(|(descriptor, _)| *descriptor == event.wd)?
+                    .1;
+
+                match only_file {
+                    Some(only_file) => (name == only_file).then_some(name),
+                    None => (!name.starts_with('.') && !name.ends_with('~')).then_some(name),
+                }
+            })
+            .collect();
+
+        Ok(names)
+    }
+}
+
+fn with_subdirectories(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut dirs = vec![dir.to_path_buf()];
+
+    // Only the day directories are walked, the src watch is just for main.rs
+    if dir != Path::new("src") {
+        for entry in dir.read_dir()? {
+            let path = entry?.path();
+            if path.is_dir() {
+                dirs.extend(with_subdirectories(&path)?);
+            }
+        }
+    }
+
+    Ok(dirs)
+}
+
+// Reads the "<problem> = <answer>" lines printed by the runner's day command
+pub fn parse_answers(output: &str) -> BTreeMap<String, String> {
+    output
+        .lines()
+        .filter_map(|line| line.split_once(" = "))
+        .map(|(problem, answer)| (problem.to_string(), answer.to_string()))
+        .collect()
+}
+
+pub fn diff_answers(
+    previous: &BTreeMap<String, String>,
+    current: &BTreeMap<String, String>,
+) -> Vec<String> {
+    current
+        .iter()
+        .map(|(problem, answer)| match previous.get(problem) {
+            Some(previous) if previous == answer => format!("  {problem} = {answer}"),
+            Some(previous) => format!("~ {problem} = {answer} (was {previous})"),
+            None => format!("+ {problem} = {answer}"),
+        })
+        .chain(
+            previous
+                .iter()
+                .filter(|(problem, _)| !current.contains_key(*problem))
+                .map(|(problem, answer)| format!("- {problem} = {answer}")),
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_diff_answers() {
+        let previous = parse_answers("a = 1 (correct)\nb = 2 (correct)\nc = 3 (correct)\n");
+        let current = parse_answers("Running...\na = 1 (correct)\nb = 5 (expected 2)\nd = 4\n");
+
+        assert_eq!(
+            diff_answers(&previous, &current),
+            vec![
+                "  a = 1 (correct)",
+                "~ b = 5 (expected 2) (was 2 (correct))",
+                "+ d = 4",
+                "- c = 3 (correct)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_wait_for_change() {
+        let dir = std::env::temp_dir().join("aoc_watch_test");
+        fs::create_dir_all(dir.join("nested")).unwrap();
+
+        let mut watcher = DayWatcher::new(&[(dir.clone(), None)]).unwrap();
+
+        fs::write(dir.join(".swap"), "ignored").unwrap();
+        fs::write(dir.join("nested").join("input.txt"), "1 2 3").unwrap();
+
+        assert_eq!(watcher.wait_for_change().unwrap(), vec!["input.txt"]);
+    }
+}
@@ -1,75 +1,287 @@
-use std::fs;
+use std::{fmt::Display, fs, io::Write};
 
 use regex::Regex;
 
 pub mod task1 {
-    use super::process_moves;
+    use super::{top_crates, CrateMover};
 
     pub fn ans() -> String {
-        process_moves(
-            "resources/2022/day05/input",
-            "resources/2022/day05/moves",
-            false,
-        )
+        top_crates("resources/2022/day05/input", CrateMover::Model9000)
     }
 }
 
 pub mod task2 {
-    use super::process_moves;
+    use super::{top_crates, CrateMover};
 
     pub fn ans() -> String {
-        process_moves(
-            "resources/2022/day05/input",
-            "resources/2022/day05/moves",
-            true,
+        top_crates("resources/2022/day05/input", CrateMover::Model9001)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CrateMover {
+    // Moves crates one at a time, reversing their order
+    Model9000,
+    // Moves all the crates at once, keeping their order
+    Model9001,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Move {
+    count: usize,
+    from: usize,
+    to: usize,
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "move {} from {} to {}",
+            self.count,
+            self.from + 1,
+            self.to + 1
         )
     }
 }
 
-fn process_moves(initial: &str, moves: &str, multiple_pickup: bool) -> String {
-    let initial_setup_contents = fs::read_to_string(initial).expect("Error reading file");
-    let split_initial_setup_contents = initial_setup_contents.lines();
+#[derive(Debug, PartialEq)]
+enum MoveError {
+    MissingStack(usize),
+    TooFewCrates { stack: usize, height: usize },
+}
+
+impl Display for MoveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            MoveError::MissingStack(stack) => write!(f, "there is no stack {}", stack + 1),
+            MoveError::TooFewCrates { stack, height } => {
+                write!(f, "stack {} is only {height} crates high", stack + 1)
+            }
+        }
+    }
+}
+
+// Each stack is listed from the bottom crate up
+#[derive(Clone, Debug, PartialEq)]
+struct Stacks(Vec<Vec<char>>);
+
+impl Stacks {
+    fn apply(&mut self, instruction: Move, crane: CrateMover) -> Result<(), MoveError> {
+        for stack in [instruction.from, instruction.to] {
+            if stack >= self.0.len() {
+                return Err(MoveError::MissingStack(stack));
+            }
+        }
+
+        let from = &mut self.0[instruction.from];
+        let height = from.len();
+        let Some(left) = height.checked_sub(instruction.count) else {
+            return Err(MoveError::TooFewCrates {
+                stack: instruction.from,
+                height,
+            });
+        };
+        let mut moved = from.split_off(left);
+
+        if crane == CrateMover::Model9000 {
+            moved.reverse();
+        }
+        self.0[instruction.to].extend(moved);
+
+        Ok(())
+    }
+
+    fn tops(&self) -> String {
+        self.0.iter().filter_map(|stack| stack.last()).collect()
+    }
+}
+
+// Draws the stacks the same way as the puzzle input, with the numbered footer
+impl Display for Stacks {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let height = self.0.iter().map(|stack| stack.len()).max().unwrap_or(0);
+
+        for level in (0..height).rev() {
+            let row: Vec<String> = self
+                .0
+                .iter()
+                .map(|stack| match stack.get(level) {
+                    Some(c) => format!("[{c}]"),
+                    None => "   ".to_string(),
+                })
+                .collect();
+            writeln!(f, "{}", row.join(" ").trim_end())?;
+        }
+
+        let footer: Vec<String> = (1..=self.0.len()).map(|i| format!(" {i} ")).collect();
+        write!(f, "{}", footer.join(" ").trim_end())
+    }
+}
+
+fn parse_file(file: &str) -> (Stacks, Vec<Move>) {
+    let contents = fs::read_to_string(file).expect("Error reading file");
+    let (drawing, moves) = contents
+        .split_once("\n\n")
+        .expect("Missing blank line after the drawing");
+
+    (parse_drawing(drawing), parse_moves(moves))
+}
 
-    let moves_contents = fs::read_to_string(moves).expect("Error reading file");
-    let split_moves_contents = moves_contents.lines();
+fn parse_drawing(drawing: &str) -> Stacks {
+    let mut lines: Vec<&str> = drawing.lines().collect();
+    let footer = lines.pop().expect("Missing stack numbers");
 
-    let mut stacks: Vec<Vec<char>> = split_initial_setup_contents
-        .map(|x| x.chars().collect())
+    // Crates sit directly above their stack's number, and the lines can stop early
+    // when the stacks on the right are shorter
+    let columns: Vec<usize> = footer
+        .char_indices()
+        .filter(|(_, c)| c.is_ascii_digit())
+        .filter(|(i, _)| !footer[i + 1..].starts_with(|c: char| c.is_ascii_digit()))
+        .map(|(i, _)| i)
         .collect();
 
-    let regex =
-        Regex::new(r"move (?P<number>\d+) from (?P<source>\d+) to (?P<destination>\d+)").unwrap();
-    let moves: Vec<[usize; 3]> = split_moves_contents
-        .map(|x| -> [usize; 3] {
-            let captures = regex.captures(x).unwrap();
-            [
-                captures["number"].parse::<usize>().unwrap(),
-                captures["source"].parse::<usize>().unwrap(),
-                captures["destination"].parse::<usize>().unwrap(),
-            ]
+    let stacks = columns
+        .iter()
+        .map(|&column| {
+            lines
+                .iter()
+                .rev()
+                .filter_map(|line| line.as_bytes().get(column))
+                .map(|&byte| byte as char)
+                .filter(|c| c.is_ascii_alphabetic())
+                .collect()
         })
         .collect();
 
-    for instruction in moves {
-        if !multiple_pickup {
-            for _ in 0..instruction[0] {
-                let moved_crate = stacks[instruction[1] - 1].pop().unwrap();
-                stacks[instruction[2] - 1].push(moved_crate);
-            }
-        } else {
-            let mut temp_stack: Vec<char> = vec![];
-            for _ in 0..instruction[0] {
-                temp_stack.push(stacks[instruction[1] - 1].pop().unwrap());
-            }
-            for _ in 0..instruction[0] {
-                stacks[instruction[2] - 1].push(temp_stack.pop().unwrap());
+    Stacks(stacks)
+}
+
+fn parse_moves(moves: &str) -> Vec<Move> {
+    let regex = Regex::new(r"move (?P<count>\d+) from (?P<from>\d+) to (?P<to>\d+)").unwrap();
+
+    moves
+        .lines()
+        .map(|line| {
+            let captures = regex
+                .captures(line)
+                .unwrap_or_else(|| panic!("Invalid move {line}"));
+            // Stacks are numbered from one
+            let stack = |name: &str| {
+                captures[name]
+                    .parse::<usize>()
+                    .unwrap()
+                    .checked_sub(1)
+                    .unwrap_or_else(|| panic!("Invalid move {line}"))
+            };
+            Move {
+                count: captures["count"].parse().unwrap(),
+                from: stack("from"),
+                to: stack("to"),
             }
+        })
+        .collect()
+}
+
+fn top_crates(file: &str, crane: CrateMover) -> String {
+    let (mut stacks, moves) = parse_file(file);
+
+    for (i, instruction) in moves.into_iter().enumerate() {
+        if let Err(error) = stacks.apply(instruction, crane) {
+            panic!("Move {}, {instruction}: {error}", i + 1);
         }
     }
 
-    return stacks
-        .iter()
-        .map(|x| String::from(x.clone().pop().unwrap()))
-        .collect::<Vec<String>>()
-        .join("");
+    stacks.tops()
+}
+
+pub fn trace_crates(file: &str, crane: CrateMover, mut output: impl Write) {
+    let (mut stacks, moves) = parse_file(file);
+
+    writeln!(output, "{stacks}\n").unwrap();
+    for (i, instruction) in moves.into_iter().enumerate() {
+        if let Err(error) = stacks.apply(instruction, crane) {
+            writeln!(output, "Move {}, {instruction}: {error}", i + 1).unwrap();
+            return;
+        }
+        writeln!(output, "{instruction}\n{stacks}\n").unwrap();
+    }
+
+    writeln!(output, "Top crates: {}", stacks.tops()).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_top_crates() {
+        assert_eq!(
+            top_crates("resources/2022/day05/test_input", CrateMover::Model9000),
+            "CMZ"
+        );
+        assert_eq!(
+            top_crates("resources/2022/day05/test_input", CrateMover::Model9001),
+            "MCD"
+        );
+    }
+
+    #[test]
+    fn test_drawing_round_trips() {
+        let contents = fs::read_to_string("resources/2022/day05/test_input").unwrap();
+        let (drawing, _) = contents.split_once("\n\n").unwrap();
+
+        assert_eq!(parse_drawing(drawing).to_string(), drawing);
+    }
+
+    #[test]
+    fn test_parse_ragged_drawing() {
+        let drawing = "[A]\n[B]         [C]\n 1   2   3   4  \n";
+
+        assert_eq!(
+            parse_drawing(drawing),
+            Stacks(vec![vec!['B', 'A'], vec![], vec![], vec!['C']])
+        );
+    }
+
+    #[test]
+    fn test_invalid_moves() {
+        let mut stacks = Stacks(vec![vec!['A'], vec!['B', 'C']]);
+        let instruction = |count, from, to| Move { count, from, to };
+
+        assert_eq!(
+            stacks.apply(instruction(2, 0, 1), CrateMover::Model9000),
+            Err(MoveError::TooFewCrates {
+                stack: 0,
+                height: 1
+            })
+        );
+        assert_eq!(
+            stacks.apply(instruction(1, 1, 2), CrateMover::Model9000),
+            Err(MoveError::MissingStack(2))
+        );
+        assert_eq!(stacks, Stacks(vec![vec!['A'], vec!['B', 'C']]));
+
+        let error = MoveError::TooFewCrates {
+            stack: 1,
+            height: 2,
+        };
+        assert_eq!(error.to_string(), "stack 2 is only 2 crates high");
+    }
+
+    #[test]
+    fn test_trace_crates() {
+        let mut output = vec![];
+        trace_crates(
+            "resources/2022/day05/test_input",
+            CrateMover::Model9001,
+            &mut output,
+        );
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.contains(
+            "move 3 from 1 to 3\n        [D]\n        [N]\n    [C] [Z]\n    [M] [P]\n 1   2   3\n"
+        ));
+        assert!(output.ends_with("Top crates: MCD\n"));
+    }
 }
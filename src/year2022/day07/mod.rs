@@ -1,120 +1,362 @@
-use std::{collections::HashMap, fs};
+use std::{collections::BTreeMap, fmt::Write as _, fs, io::Write};
 
 pub mod task1 {
-    use super::{calculate_directory_sizes, sum_large_directories};
+    use super::{sum_small_directories, FileSystem};
 
     pub fn ans() -> u128 {
-        let mut directories: Vec<u128> = vec![];
-        calculate_directory_sizes("resources/2022/day07/input", &mut directories);
+        let filesystem = FileSystem::from_file("resources/2022/day07/input");
 
-        sum_large_directories(&directories, 100_000)
+        sum_small_directories(&filesystem, 100_000) as u128
     }
 }
 
 pub mod task2 {
-    use super::{calculate_directory_sizes, find_smallest_sufficient_directory};
+    use super::{FileSystem, DISK_SIZE};
 
     pub fn ans() -> u128 {
-        let mut directories: Vec<u128> = vec![];
-        calculate_directory_sizes("resources/2022/day07/input", &mut directories);
+        let filesystem = FileSystem::from_file("resources/2022/day07/input");
 
-        find_smallest_sufficient_directory(&mut directories, 30000000)
+        let (_, size) = filesystem
+            .smallest_to_free(DISK_SIZE, 30_000_000)
+            .expect("No directory is large enough, or the files don't fit on the disk");
+        size as u128
     }
 }
 
-fn calculate_directory_sizes(file: &str, directories: &mut Vec<u128>) {
-    let input_contents = fs::read_to_string(file).expect("Error reading file");
-    let mut lines = input_contents.lines();
+const DISK_SIZE: u64 = 70_000_000;
+const ROOT: usize = 0;
 
-    if lines.next().unwrap() != "$ cd /" {
-        panic!();
-    }
+#[derive(Clone, Debug)]
+enum Node {
+    Dir(Dir),
+    File(File),
+}
 
-    let mut directory: Vec<&str> = vec!["root"];
-    let mut directory_size: u128 = 0;
-    let mut hash_map: HashMap<String, u128> = HashMap::new();
-    loop {
-        let cmd_option = lines.next();
-        if cmd_option.is_none() {
-            break;
+#[derive(Clone, Debug)]
+struct Dir {
+    name: String,
+    parent: usize,
+    children: BTreeMap<String, usize>,
+}
+
+#[derive(Clone, Debug)]
+struct File {
+    name: String,
+    parent: usize,
+    size: u64,
+}
+
+// Nodes live in one arena and refer to each other by index, with the root first.
+// The root is its own parent, so `cd ..` at the top stays there like a real shell
+#[derive(Clone, Debug)]
+struct FileSystem {
+    nodes: Vec<Node>,
+}
+
+impl FileSystem {
+    fn new() -> Self {
+        FileSystem {
+            nodes: vec![Node::Dir(Dir {
+                name: "/".to_string(),
+                parent: ROOT,
+                children: BTreeMap::new(),
+            })],
         }
-        let cmd = cmd_option.unwrap();
-
-        if cmd == "$ cd .." {
-            directory.pop();
-            hash_map
-                .entry(directory.clone().join("/"))
-                .and_modify(|size| {
-                    *size += directory_size;
-                })
-                .or_insert(directory_size);
-            directory_size = *hash_map.get(&directory.join("/")).unwrap();
-        } else if cmd == "$ ls" {
-            while lines
-                .clone()
-                .peekable()
-                .peek()
-                .unwrap_or(&" ")
-                .split_at(1)
-                .0
-                != "$"
-            {
-                let item = lines.next().unwrap_or(" ");
-                if item == " " {
-                    break;
+    }
+
+    fn from_file(file: &str) -> Self {
+        let contents = fs::read_to_string(file).expect("Error reading file");
+        Self::from_transcript(&contents)
+    }
+
+    // Replays a terminal log. Listing a directory twice or changing into one that hasn't
+    // been listed yet can't create duplicates, as every entry is looked up by name first
+    fn from_transcript(transcript: &str) -> Self {
+        let mut filesystem = FileSystem::new();
+        let mut current = ROOT;
+
+        for (i, line) in transcript.lines().enumerate() {
+            match line.split(' ').collect::<Vec<_>>().as_slice() {
+                ["$", "cd", "/"] => current = ROOT,
+                ["$", "cd", ".."] => current = filesystem.dir(current).parent,
+                ["$", "cd", name] => {
+                    current = filesystem.mkdir(current, name);
+                    if let Node::File(file) = &filesystem.nodes[current] {
+                        panic!(
+                            "Line {}: can't cd into {}, as it's a file",
+                            i + 1,
+                            file.name
+                        );
+                    }
+                }
+                ["$", "ls"] => {}
+                ["dir", name] => {
+                    filesystem.mkdir(current, name);
                 }
-                if item.split_at(3).0 != "dir" {
-                    directory_size +=
-                        item.split(' ').next().unwrap().parse::<u128>().unwrap();
+                [size, name] => {
+                    let size = size
+                        .parse()
+                        .unwrap_or_else(|_| panic!("Invalid line {line}"));
+                    filesystem.touch(current, name, size);
                 }
+                _ => panic!("Invalid line {line}"),
             }
-            hash_map
-                .entry(directory.join("/"))
-                .and_modify(|size| {
-                    *size += directory_size;
-                })
-                .or_insert(directory_size);
-        } else {
-            hash_map.entry(directory.join("/"));
-            directory.push(cmd.split_at(5).1);
-            directory_size = 0;
+        }
+
+        filesystem
+    }
+
+    fn dir(&self, node: usize) -> &Dir {
+        match &self.nodes[node] {
+            Node::Dir(dir) => dir,
+            Node::File(file) => panic!("{} is not a directory", file.name),
         }
     }
 
-    while directory.len() > 1 {
-        directory.pop();
-        hash_map
-            .entry(directory.clone().join("/"))
-            .and_modify(|size| {
-                *size += directory_size;
+    fn add_child(&mut self, parent: usize, name: &str, node: Node) -> usize {
+        if let Some(&existing) = self.dir(parent).children.get(name) {
+            return existing;
+        }
+
+        let index = self.nodes.len();
+        self.nodes.push(node);
+        match &mut self.nodes[parent] {
+            Node::Dir(dir) => dir.children.insert(name.to_string(), index),
+            Node::File(_) => unreachable!(),
+        };
+
+        index
+    }
+
+    fn mkdir(&mut self, parent: usize, name: &str) -> usize {
+        let dir = Node::Dir(Dir {
+            name: name.to_string(),
+            parent,
+            children: BTreeMap::new(),
+        });
+        self.add_child(parent, name, dir)
+    }
+
+    fn touch(&mut self, parent: usize, name: &str, size: u64) {
+        let file = Node::File(File {
+            name: name.to_string(),
+            parent,
+            size,
+        });
+        self.add_child(parent, name, file);
+    }
+
+    fn du(&self, node: usize) -> u64 {
+        match &self.nodes[node] {
+            Node::Dir(dir) => dir.children.values().map(|&child| self.du(child)).sum(),
+            Node::File(file) => file.size,
+        }
+    }
+
+    // Sizes of every directory, worked out in a single pass from the leaves up
+    fn directory_sizes(&self) -> Vec<(usize, u64)> {
+        let mut sizes = vec![0; self.nodes.len()];
+
+        // Children are always added after their parents
+        for (i, node) in self.nodes.iter().enumerate().rev() {
+            sizes[i] = match node {
+                Node::Dir(dir) => dir.children.values().map(|&child| sizes[child]).sum(),
+                Node::File(file) => file.size,
+            };
+        }
+
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| matches!(node, Node::Dir(_)))
+            .map(|(i, _)| (i, sizes[i]))
+            .collect()
+    }
+
+    fn path(&self, node: usize) -> String {
+        if node == ROOT {
+            return "/".to_string();
+        }
+
+        let mut names = vec![];
+        let mut current = node;
+        while current != ROOT {
+            let (name, parent) = match &self.nodes[current] {
+                Node::Dir(dir) => (&dir.name, dir.parent),
+                Node::File(file) => (&file.name, file.parent),
+            };
+            names.push(name.as_str());
+            current = parent;
+        }
+
+        names.iter().rev().map(|name| format!("/{name}")).collect()
+    }
+
+    fn lookup(&self, path: &str) -> Option<usize> {
+        path.split('/')
+            .filter(|name| !name.is_empty())
+            .try_fold(ROOT, |current, name| match &self.nodes[current] {
+                Node::Dir(dir) => dir.children.get(name).copied(),
+                Node::File(_) => None,
             })
-            .or_insert(directory_size);
-        directory_size = *hash_map.get(&directory.clone().join("/")).unwrap();
     }
 
-    for (_, value) in hash_map.iter() {
-        directories.push(*value);
+    // Renders the same way as the example in the puzzle
+    fn tree(&self, node: usize) -> String {
+        let mut output = String::new();
+        self.write_tree(node, 0, &mut output);
+        output
+    }
+
+    fn write_tree(&self, node: usize, depth: usize, output: &mut String) {
+        let indent = "  ".repeat(depth);
+
+        match &self.nodes[node] {
+            Node::Dir(dir) => {
+                writeln!(output, "{indent}- {} (dir)", dir.name).unwrap();
+                for &child in dir.children.values() {
+                    self.write_tree(child, depth + 1, output);
+                }
+            }
+            Node::File(file) => {
+                writeln!(output, "{indent}- {} (file, size={})", file.name, file.size).unwrap()
+            }
+        }
+    }
+
+    // The smallest directory that frees enough space once deleted, as its path and size.
+    // There's none if the files don't fit on the disk in the first place
+    fn smallest_to_free(&self, disk_size: u64, required: u64) -> Option<(String, u64)> {
+        let unused = disk_size.checked_sub(self.du(ROOT))?;
+        let to_free = required.saturating_sub(unused);
+
+        self.directory_sizes()
+            .into_iter()
+            .filter(|(_, size)| *size >= to_free)
+            .min_by_key(|(_, size)| *size)
+            .map(|(dir, size)| (self.path(dir), size))
     }
 }
 
-fn sum_large_directories(directories: &Vec<u128>, max_size: u128) -> u128 {
-    let mut sum: u128 = 0;
-    for size in directories {
-        if *size <= max_size {
-            sum += size;
+fn sum_small_directories(filesystem: &FileSystem, max_size: u64) -> u64 {
+    filesystem
+        .directory_sizes()
+        .into_iter()
+        .map(|(_, size)| size)
+        .filter(|size| *size <= max_size)
+        .sum()
+}
+
+pub fn explore_filesystem(file: &str, path: &str, mut output: impl Write) {
+    let filesystem = FileSystem::from_file(file);
+    let node = filesystem
+        .lookup(path)
+        .unwrap_or_else(|| panic!("No such file or directory {path}"));
+
+    write!(output, "{}", filesystem.tree(node)).unwrap();
+    writeln!(output, "{} {}", filesystem.du(node), filesystem.path(node)).unwrap();
+
+    match filesystem.smallest_to_free(DISK_SIZE, 30_000_000) {
+        Some((path, size)) => writeln!(output, "Delete {path} to free {size}").unwrap(),
+        None if filesystem.du(ROOT) > DISK_SIZE => {
+            writeln!(output, "The files don't fit on a disk of {DISK_SIZE}").unwrap()
         }
+        None => writeln!(output, "No directory frees enough space").unwrap(),
     }
-    sum
 }
 
-fn find_smallest_sufficient_directory(directories: &mut [u128], required_space: u128) -> u128 {
-    directories.sort();
-    let iter_dir = directories.iter().rev();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sum_small_directories() {
+        let filesystem = FileSystem::from_file("resources/2022/day07/test_input");
+
+        assert_eq!(sum_small_directories(&filesystem, 100_000), 95437);
+    }
+
+    #[test]
+    fn test_smallest_to_free() {
+        let filesystem = FileSystem::from_file("resources/2022/day07/test_input");
 
-    let necessary_deletion = required_space - (70000000u128 - iter_dir.clone().next().unwrap());
+        assert_eq!(
+            filesystem.smallest_to_free(DISK_SIZE, 30_000_000),
+            Some(("/d".to_string(), 24933642))
+        );
 
-    *iter_dir
-        .filter(|x| x >= &&necessary_deletion)
-        .last()
-        .unwrap()
+        // The example's files take up 48381165
+        assert_eq!(filesystem.smallest_to_free(40_000_000, 30_000_000), None);
+    }
+
+    #[test]
+    fn test_tree() {
+        let filesystem = FileSystem::from_file("resources/2022/day07/test_input");
+
+        assert_eq!(
+            filesystem.tree(ROOT),
+            "- / (dir)
+  - a (dir)
+    - e (dir)
+      - i (file, size=584)
+    - f (file, size=29116)
+    - g (file, size=2557)
+    - h.lst (file, size=62596)
+  - b.txt (file, size=14848514)
+  - c.dat (file, size=8504156)
+  - d (dir)
+    - d.ext (file, size=5626152)
+    - d.log (file, size=8033020)
+    - j (file, size=4060174)
+    - k (file, size=7214296)
+"
+        );
+    }
+
+    #[test]
+    fn test_lookup() {
+        let filesystem = FileSystem::from_file("resources/2022/day07/test_input");
+
+        let e = filesystem.lookup("/a/e").unwrap();
+        assert_eq!(filesystem.du(e), 584);
+        assert_eq!(filesystem.path(e), "/a/e");
+        assert_eq!(
+            filesystem.lookup("/a/e/i").map(|i| filesystem.path(i)),
+            Some("/a/e/i".to_string())
+        );
+        assert_eq!(filesystem.lookup("/a/x"), None);
+        assert_eq!(filesystem.lookup("/"), Some(ROOT));
+    }
+
+    #[test]
+    fn test_unusual_transcripts() {
+        // Starts without `cd /`, changes into a directory before it's listed, lists
+        // directories twice and jumps back to the root partway through
+        let filesystem = FileSystem::from_transcript(
+            "$ cd a
+$ ls
+10 x
+$ cd /
+$ ls
+dir a
+5 y
+$ cd a
+$ ls
+10 x
+$ cd ..
+$ cd ..
+$ ls
+5 y",
+        );
+
+        assert_eq!(filesystem.du(ROOT), 15);
+        assert_eq!(filesystem.du(filesystem.lookup("/a").unwrap()), 10);
+        assert_eq!(filesystem.directory_sizes(), vec![(ROOT, 15), (1, 10)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Line 4: can't cd into x, as it's a file")]
+    fn test_cd_into_file() {
+        FileSystem::from_transcript("$ cd /\n$ ls\n10 x\n$ cd x\n$ ls");
+    }
 }
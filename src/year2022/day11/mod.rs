@@ -1,132 +1,381 @@
-use std::fs;
+use std::{
+    fmt::Display,
+    fs,
+    io::Write,
+    ops::{Add, Mul},
+};
 
+use num::{integer::lcm, BigUint, Zero};
 use regex::Regex;
 
 pub mod task1 {
-    use super::product_top_worries;
+    use super::{product_top_worries, Relief};
 
     pub fn ans() -> u128 {
-        product_top_worries("resources/2022/day11/input", 20, true)
+        product_top_worries("resources/2022/day11/input", 20, Relief::DivideBy(3))
     }
 }
 
 pub mod task2 {
-    use super::product_top_worries;
+    use super::{product_top_worries, Relief};
 
     pub fn ans() -> u128 {
-        product_top_worries("resources/2022/day11/input", 10000, false)
+        product_top_worries("resources/2022/day11/input", 10000, Relief::Modulus)
     }
 }
 
-type Rule = (
-    Vec<isize>,
-    Box<dyn Fn(isize) -> isize>,
-    usize,
-    usize,
-    usize,
-    usize,
-);
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    Old,
+    Constant(u64),
+    Add(Box<Expr>, Box<Expr>),
+    Multiply(Box<Expr>, Box<Expr>),
+}
 
-fn parse_rules(file: &str) -> Vec<Rule> {
-    let contents = fs::read_to_string(file).expect("Error reading file");
+impl Expr {
+    fn evaluate<W>(&self, old: &W) -> W
+    where
+        W: Clone + From<u64> + Add<Output = W> + Mul<Output = W>,
+    {
+        match self {
+            Expr::Old => old.clone(),
+            Expr::Constant(value) => W::from(*value),
+            Expr::Add(a, b) => a.evaluate(old) + b.evaluate(old),
+            Expr::Multiply(a, b) => a.evaluate(old) * b.evaluate(old),
+        }
+    }
 
-    let regex = Regex::new(
-        r"Monkey \d+:\n  Starting items: (?P<items>(?:\d+,?\s?)+)\n  Operation: new = (?P<operand1>[^ ]+) (?P<operator>.) (?P<operand2>[^ ]+)\n  Test: divisible by (?P<divisor>\d+)\n    If true: throw to monkey (?P<true>\d+)\n    If false: throw to monkey (?P<false>\d+)"
-    ).unwrap();
+    // Reducing after every operation keeps the intermediate values below modulus squared
+    fn evaluate_mod(&self, old: u64, modulus: u64) -> u64 {
+        let value = match self {
+            Expr::Old => old as u128,
+            Expr::Constant(value) => *value as u128,
+            Expr::Add(a, b) => {
+                a.evaluate_mod(old, modulus) as u128 + b.evaluate_mod(old, modulus) as u128
+            }
+            Expr::Multiply(a, b) => {
+                a.evaluate_mod(old, modulus) as u128 * b.evaluate_mod(old, modulus) as u128
+            }
+        };
+
+        (value % modulus as u128) as u64
+    }
+}
+
+impl Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let operand = |expr: &Expr| match expr {
+            Expr::Add(..) | Expr::Multiply(..) => format!("({expr})"),
+            _ => expr.to_string(),
+        };
 
-    let mut rules: Vec<Rule> = vec![];
-    for capture in regex.captures_iter(&contents) {
-        let mut monkey: Rule = (vec![], Box::new(move |_| 0), 0, 0, 0, 0);
-        for item in capture["items"].split(", ") {
-            monkey.0.push(item.parse::<isize>().unwrap());
+        match self {
+            Expr::Old => write!(f, "old"),
+            Expr::Constant(value) => write!(f, "{value}"),
+            Expr::Add(a, b) => write!(f, "{} + {}", operand(a), operand(b)),
+            Expr::Multiply(a, b) => write!(f, "{} * {}", operand(a), operand(b)),
         }
+    }
+}
 
-        monkey.1 = generate_closure(
-            capture["operator"].to_owned(),
-            capture["operand1"].to_owned(),
-            capture["operand2"].to_owned(),
-        );
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Relief {
+    // Worry is divided after every inspection, as in part 1
+    DivideBy(u64),
+    // Worry is kept modulo every monkey's divisor, which doesn't change where items are thrown
+    Modulus,
+    // Worry grows without limit, which needs big integers
+    None,
+}
 
-        monkey.2 = capture["divisor"].parse::<usize>().unwrap();
-        monkey.3 = capture["true"].parse::<usize>().unwrap();
-        monkey.4 = capture["false"].parse::<usize>().unwrap();
+trait Worry: Clone + Display + From<u64> + Add<Output = Self> + Mul<Output = Self> {
+    fn inspect(&self, operation: &Expr, relief: Relief, modulus: u64) -> Self;
+    fn is_divisible_by(&self, divisor: u64) -> bool;
+}
+
+impl Worry for u64 {
+    fn inspect(&self, operation: &Expr, relief: Relief, modulus: u64) -> Self {
+        if relief == Relief::Modulus {
+            return operation.evaluate_mod(*self, modulus);
+        }
 
-        rules.push(monkey);
+        let worry: u64 = operation
+            .evaluate(&(*self as u128))
+            .try_into()
+            .expect("Worry overflowed, try big integer mode");
+
+        match relief {
+            Relief::DivideBy(divisor) => worry / divisor,
+            _ => worry,
+        }
     }
 
-    rules
+    fn is_divisible_by(&self, divisor: u64) -> bool {
+        self.is_multiple_of(divisor)
+    }
 }
 
-fn generate_closure<'a>(
-    operator: String,
-    operand1: String,
-    operand2: String,
-) -> Box<dyn (Fn(isize) -> isize) + 'a> {
-    let inner_closure: Box<dyn Fn(isize, isize) -> isize>;
-    if operator == "+" {
-        inner_closure = Box::new(move |a, b| a + b);
-    } else if operator == "*" {
-        inner_closure = Box::new(move |a, b| a * b);
-    } else {
-        panic!("Invalid operation");
+impl Worry for BigUint {
+    fn inspect(&self, operation: &Expr, relief: Relief, modulus: u64) -> Self {
+        let worry = operation.evaluate(self);
+
+        match relief {
+            Relief::DivideBy(divisor) => worry / divisor,
+            Relief::Modulus => worry % modulus,
+            Relief::None => worry,
+        }
     }
 
-    let mid_closure: Box<dyn Fn(isize, isize) -> isize> = if operand1 == "old" {
-        Box::new(inner_closure)
-    } else {
-        Box::new(move |_, b| inner_closure(operand1.parse::<isize>().unwrap(), b))
-    };
+    fn is_divisible_by(&self, divisor: u64) -> bool {
+        (self % divisor).is_zero()
+    }
+}
 
-    if operand2 == "old" {
-        Box::new(move |old| mid_closure(old, old))
-    } else {
-        Box::new(move |old| mid_closure(old, operand2.parse::<isize>().unwrap()))
+#[derive(Clone, Debug)]
+struct Monkey<W> {
+    items: Vec<W>,
+    operation: Expr,
+    divisor: u64,
+    if_true: usize,
+    if_false: usize,
+    inspections: usize,
+}
+
+impl<W: Display> Display for Monkey<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let items: Vec<String> = self.items.iter().map(|item| item.to_string()).collect();
+
+        writeln!(f, "  Starting items: {}", items.join(", "))?;
+        writeln!(f, "  Operation: new = {}", self.operation)?;
+        writeln!(f, "  Test: divisible by {}", self.divisor)?;
+        writeln!(f, "    If true: throw to monkey {}", self.if_true)?;
+        write!(f, "    If false: throw to monkey {}", self.if_false)
     }
 }
 
-fn product_top_worries(file: &str, rounds: usize, divide_by_three: bool) -> u128 {
-    let mut rules = parse_rules(file);
+fn parse_monkeys<W: Worry>(file: &str) -> Vec<Monkey<W>> {
+    let contents = fs::read_to_string(file).expect("Error reading file");
+
+    let regex = Regex::new(
+        r"Monkey \d+:\n  Starting items: (?P<items>(?:\d+,?\s?)+)\n  Operation: new = (?P<operand1>[^ ]+) (?P<operator>.) (?P<operand2>[^ ]+)\n  Test: divisible by (?P<divisor>\d+)\n    If true: throw to monkey (?P<true>\d+)\n    If false: throw to monkey (?P<false>\d+)"
+    ).unwrap();
 
-    for _ in 0..rounds {
-        make_moves(&mut rules, divide_by_three);
+    regex
+        .captures_iter(&contents)
+        .map(|capture| Monkey {
+            items: capture["items"]
+                .split(", ")
+                .map(|item| W::from(item.trim().parse::<u64>().unwrap()))
+                .collect(),
+            operation: parse_expr(
+                &capture["operand1"],
+                &capture["operator"],
+                &capture["operand2"],
+            ),
+            divisor: capture["divisor"].parse().unwrap(),
+            if_true: capture["true"].parse().unwrap(),
+            if_false: capture["false"].parse().unwrap(),
+            inspections: 0,
+        })
+        .collect()
+}
+
+fn parse_expr(operand1: &str, operator: &str, operand2: &str) -> Expr {
+    let operand = |operand: &str| match operand {
+        "old" => Expr::Old,
+        value => Expr::Constant(value.parse().expect("Invalid operand")),
+    };
+
+    let (a, b) = (Box::new(operand(operand1)), Box::new(operand(operand2)));
+    match operator {
+        "+" => Expr::Add(a, b),
+        "*" => Expr::Multiply(a, b),
+        _ => panic!("Invalid operation"),
     }
+}
 
-    let mut inspections: Vec<u128> = rules.iter().map(|monkey| monkey.5 as u128).collect();
+fn product_top_worries(file: &str, rounds: usize, relief: Relief) -> u128 {
+    let mut monkeys = parse_monkeys::<u64>(file);
+
+    play_rounds(&mut monkeys, rounds, relief, |_, _| {});
+
+    monkey_business(&monkeys)
+}
+
+fn monkey_business<W>(monkeys: &[Monkey<W>]) -> u128 {
+    let mut inspections: Vec<u128> = monkeys
+        .iter()
+        .map(|monkey| monkey.inspections as u128)
+        .collect();
 
     inspections.sort_by(|a, b| b.cmp(a));
 
     inspections[0] * inspections[1]
 }
 
-fn make_moves(monkeys: &mut Vec<Rule>, divide_by_three: bool) {
-    let divisor = find_divisor(monkeys);
-    for i in 0..monkeys.len() {
-        monkeys[i].5 += monkeys[i].0.len();
-        for item in 0..monkeys[i].0.len() {
-            let mut worry = monkeys[i].0[item];
+fn play_rounds<W: Worry>(
+    monkeys: &mut [Monkey<W>],
+    rounds: usize,
+    relief: Relief,
+    mut after_round: impl FnMut(usize, &[Monkey<W>]),
+) {
+    // Every divisor divides this, so working modulo it doesn't change any of the tests
+    let modulus = monkeys.iter().map(|monkey| monkey.divisor).fold(1, lcm);
 
-            worry = (monkeys[i].1)(worry);
-            if divide_by_three {
-                worry /= 3;
-            } else {
-                worry %= divisor;
-            }
+    for round in 1..=rounds {
+        for i in 0..monkeys.len() {
+            let items = std::mem::take(&mut monkeys[i].items);
+            monkeys[i].inspections += items.len();
+
+            for item in items {
+                let monkey = &monkeys[i];
+                let worry = item.inspect(&monkey.operation, relief, modulus);
 
-            let next_monkey = if worry % (monkeys[i].2 as isize) == 0 {
-                monkeys[i].3
-            } else {
-                monkeys[i].4
-            };
-            monkeys[next_monkey].0.push(worry);
+                let next_monkey = if worry.is_divisible_by(monkey.divisor) {
+                    monkey.if_true
+                } else {
+                    monkey.if_false
+                };
+                monkeys[next_monkey].items.push(worry);
+            }
         }
-        monkeys[i].0 = vec![];
+
+        after_round(round, monkeys);
+    }
+}
+
+pub fn trace_keep_away(
+    file: &str,
+    rounds: usize,
+    relief: Relief,
+    big_integers: bool,
+    output: impl Write,
+) {
+    if big_integers {
+        trace_rounds(parse_monkeys::<BigUint>(file), rounds, relief, output);
+    } else {
+        trace_rounds(parse_monkeys::<u64>(file), rounds, relief, output);
     }
 }
 
-fn find_divisor(monkeys: &Vec<Rule>) -> isize {
-    let mut prod: isize = 1;
-    for monkey in monkeys {
-        prod *= monkey.2 as isize;
+fn trace_rounds<W: Worry>(
+    mut monkeys: Vec<Monkey<W>>,
+    rounds: usize,
+    relief: Relief,
+    mut output: impl Write,
+) {
+    for (i, monkey) in monkeys.iter().enumerate() {
+        writeln!(output, "Monkey {i}:\n{monkey}\n").unwrap();
+    }
+
+    play_rounds(&mut monkeys, rounds, relief, |round, monkeys| {
+        writeln!(output, "== After round {round} ==").unwrap();
+        for (i, monkey) in monkeys.iter().enumerate() {
+            let items: Vec<String> = monkey.items.iter().map(|item| item.to_string()).collect();
+            writeln!(output, "Monkey {i}: {}", items.join(", ")).unwrap();
+        }
+        for (i, monkey) in monkeys.iter().enumerate() {
+            writeln!(
+                output,
+                "Monkey {i} inspected items {} times.",
+                monkey.inspections
+            )
+            .unwrap();
+        }
+    });
+
+    writeln!(output, "Monkey business: {}", monkey_business(&monkeys)).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num::ToPrimitive;
+
+    #[test]
+    fn test_product_top_worries() {
+        assert_eq!(
+            product_top_worries("resources/2022/day11/test_input", 20, Relief::DivideBy(3)),
+            10605
+        );
+    }
+
+    #[test]
+    fn test_product_top_worries_without_relief() {
+        assert_eq!(
+            product_top_worries("resources/2022/day11/test_input", 10000, Relief::Modulus),
+            2713310158
+        );
+    }
+
+    #[test]
+    fn test_display_round_trips() {
+        let monkeys = parse_monkeys::<u64>("resources/2022/day11/test_input");
+
+        let printed: Vec<String> = monkeys
+            .iter()
+            .enumerate()
+            .map(|(i, monkey)| format!("Monkey {i}:\n{monkey}"))
+            .collect();
+
+        assert_eq!(
+            printed.join("\n\n") + "\n",
+            fs::read_to_string("resources/2022/day11/test_input").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_evaluate_mod() {
+        let square_plus_one = Expr::Add(
+            Box::new(Expr::Multiply(Box::new(Expr::Old), Box::new(Expr::Old))),
+            Box::new(Expr::Constant(1)),
+        );
+
+        assert_eq!(square_plus_one.to_string(), "(old * old) + 1");
+        assert_eq!(square_plus_one.evaluate(&12u64), 145);
+        assert_eq!(square_plus_one.evaluate_mod(u64::MAX - 1, u64::MAX), 2);
+    }
+
+    #[test]
+    fn test_big_integers_match_modulus() {
+        // Without any relief the worries soon overflow, but big integers give the
+        // same throws as working modulo the divisors
+        let mut big = parse_monkeys::<BigUint>("resources/2022/day11/test_input");
+        let mut small = parse_monkeys::<u64>("resources/2022/day11/test_input");
+
+        play_rounds(&mut big, 30, Relief::None, |_, _| {});
+        play_rounds(&mut small, 30, Relief::Modulus, |_, _| {});
+
+        let modulus = 23 * 19 * 13 * 17;
+        for (big, small) in big.iter().zip(&small) {
+            assert_eq!(big.inspections, small.inspections);
+
+            let reduced: Vec<u64> = big
+                .items
+                .iter()
+                .map(|item| (item % modulus as u64).to_u64().unwrap())
+                .collect();
+            assert_eq!(reduced, small.items);
+        }
+    }
+
+    #[test]
+    fn test_trace_keep_away() {
+        let mut output = vec![];
+        trace_keep_away(
+            "resources/2022/day11/test_input",
+            20,
+            Relief::Modulus,
+            false,
+            &mut output,
+        );
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.starts_with("Monkey 0:\n  Starting items: 79, 98\n"));
+        assert!(output.contains(
+            "Monkey 0 inspected items 2 times.\nMonkey 1 inspected items 4 times.\n\
+             Monkey 2 inspected items 3 times.\nMonkey 3 inspected items 6 times.\n"
+        ));
+        assert!(output.ends_with("Monkey business: 10197\n"));
     }
-    prod
 }
@@ -1,8 +1,7 @@
-use itertools::Itertools;
-use json::number::Number;
-use json::{parse, JsonValue};
-use std::cmp::Ordering::{self, Equal, Greater, Less};
+use std::cmp::Ordering;
+use std::fmt::Display;
 use std::fs;
+use std::str::FromStr;
 
 pub mod task1 {
     use super::check_file_sorting;
@@ -20,79 +19,156 @@ pub mod task2 {
     }
 }
 
-fn check_file_sorting(file: &str) -> usize {
-    let contents = fs::read_to_string(file).expect("Error reading file");
-    contents
-        .split("\n\n")
-        .enumerate()
-        .filter(|(_, packets)| {
-            let mut lines = packets.lines().map(parse);
-            let line_1 = lines.next().unwrap().unwrap();
-            let line_2 = lines.next().unwrap().unwrap();
-
-            let sort = sorted_pair(line_1.clone(), line_2.clone());
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Packet {
+    Int(u64),
+    List(Vec<Packet>),
+}
 
-            sort == Less
-        })
-        .map(|(i, _)| i + 1)
-        .sum()
+impl Ord for Packet {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Lists compare element by element, with the shorter list first if they match,
+        // which is exactly how slices compare. A lone integer compares as a list of one
+        match (self, other) {
+            (Packet::Int(a), Packet::Int(b)) => a.cmp(b),
+            (Packet::List(a), Packet::List(b)) => a.cmp(b),
+            (Packet::Int(_), Packet::List(b)) => std::slice::from_ref(self).cmp(b),
+            (Packet::List(a), Packet::Int(_)) => a.as_slice().cmp(std::slice::from_ref(other)),
+        }
+    }
 }
 
-fn sorted_pair(element_1: JsonValue, element_2: JsonValue) -> Ordering {
-    match (element_1, element_2) {
-        (JsonValue::Array(a), JsonValue::Array(b)) => {
-            let a_len = a.len();
-            let b_len = b.len();
+impl PartialOrd for Packet {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
 
-            for (a, b) in a.into_iter().zip(b.into_iter()) {
-                let sorted_pair = sorted_pair(a, b);
-                if sorted_pair != Equal {
-                    return sorted_pair;
+impl Display for Packet {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Packet::Int(value) => write!(f, "{value}"),
+            Packet::List(packets) => {
+                write!(f, "[")?;
+                for (i, packet) in packets.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{packet}")?;
                 }
+                write!(f, "]")
             }
-            if b_len < a_len {
-                return Greater;
-            } else if b_len > a_len {
-                return Less;
-            }
-            return Equal;
         }
-        (JsonValue::Number(a), JsonValue::Number(b)) => {
-            let a = unwrap_number(a);
-            let b = unwrap_number(b);
-            return a.cmp(&b);
-        }
-        (a, JsonValue::Number(b)) => {
-            let b = JsonValue::Array(vec![JsonValue::Number(b)]);
-            return sorted_pair(a, b);
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum PacketError {
+    UnexpectedEnd,
+    UnexpectedCharacter(char),
+    TrailingInput,
+}
+
+impl Display for PacketError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PacketError::UnexpectedEnd => write!(f, "packet ended early"),
+            PacketError::UnexpectedCharacter(c) => write!(f, "unexpected character {c:?}"),
+            PacketError::TrailingInput => write!(f, "input continues after the packet"),
         }
-        (JsonValue::Number(a), b) => {
-            let a = JsonValue::Array(vec![JsonValue::Number(a)]);
-            return sorted_pair(a, b);
+    }
+}
+
+impl FromStr for Packet {
+    type Err = PacketError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (packet, rest) = parse_packet(s.as_bytes())?;
+
+        if !rest.is_empty() {
+            return Err(PacketError::TrailingInput);
         }
-        (_, _) => panic!("Yikes"),
+        Ok(packet)
     }
 }
 
-fn unwrap_number(number: Number) -> usize {
-    match usize::try_from(number) {
-        Ok(a) => a,
-        Err(_) => panic!("Yikes"),
+// Parses one packet from the start of the input, returning it with the rest of the input
+fn parse_packet(input: &[u8]) -> Result<(Packet, &[u8]), PacketError> {
+    match input.first() {
+        None => Err(PacketError::UnexpectedEnd),
+        Some(b'[') => {
+            let mut rest = &input[1..];
+            let mut packets = vec![];
+
+            if rest.first() == Some(&b']') {
+                return Ok((Packet::List(packets), &rest[1..]));
+            }
+
+            loop {
+                let (packet, after) = parse_packet(rest)?;
+                packets.push(packet);
+
+                match after.first() {
+                    Some(b',') => rest = &after[1..],
+                    Some(b']') => return Ok((Packet::List(packets), &after[1..])),
+                    Some(byte) => return Err(PacketError::UnexpectedCharacter(*byte as char)),
+                    None => return Err(PacketError::UnexpectedEnd),
+                }
+            }
+        }
+        Some(byte) if byte.is_ascii_digit() => {
+            let digits = input
+                .iter()
+                .take_while(|byte| byte.is_ascii_digit())
+                .count();
+            let value = input[..digits]
+                .iter()
+                .fold(0, |value, digit| value * 10 + (digit - b'0') as u64);
+
+            Ok((Packet::Int(value), &input[digits..]))
+        }
+        Some(byte) => Err(PacketError::UnexpectedCharacter(*byte as char)),
     }
 }
 
-fn decoder_key(file: &str) -> usize {
-    let dividers = vec![json::parse("[[2]]").unwrap(), json::parse("[[6]]").unwrap()];
+fn parse_line(line: &str) -> Packet {
+    line.parse()
+        .unwrap_or_else(|error| panic!("Invalid packet {line}: {error}"))
+}
 
+fn check_file_sorting(file: &str) -> usize {
     let contents = fs::read_to_string(file).expect("Error reading file");
     contents
+        .split("\n\n")
+        .enumerate()
+        .filter(|(_, packets)| {
+            let mut lines = packets.lines().map(parse_line);
+            let packet_1 = lines.next().unwrap();
+            let packet_2 = lines.next().unwrap();
+
+            packet_1 < packet_2
+        })
+        .map(|(i, _)| i + 1)
+        .sum()
+}
+
+fn decoder_key(file: &str) -> usize {
+    let dividers = [parse_line("[[2]]"), parse_line("[[6]]")];
+
+    let contents = fs::read_to_string(file).expect("Error reading file");
+    let mut packets: Vec<Packet> = contents
         .lines()
         .filter(|line| !line.is_empty())
-        .map(|line| parse(line).unwrap())
-        .chain(dividers.clone().into_iter())
-        .sorted_by(|a, b| sorted_pair(a.clone(), b.clone()))
+        .map(parse_line)
+        .chain(dividers.clone())
+        .collect();
+
+    packets.sort();
+
+    packets
+        .iter()
         .enumerate()
-        .filter(|(_, line)| dividers.contains(line))
+        .filter(|(_, packet)| dividers.contains(packet))
         .map(|(i, _)| i + 1)
         .product()
 }
@@ -100,6 +176,7 @@ fn decoder_key(file: &str) -> usize {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::cmp::Ordering::Greater;
 
     #[test]
     fn test_check_file_sorting() {
@@ -116,9 +193,32 @@ mod tests {
 
     #[test]
     fn test_sort() {
-        let a = json::parse("[[1],4]").unwrap();
-        let b = json::parse("[1,1,3,1,1]").unwrap();
+        let a = parse_line("[[1],4]");
+        let b = parse_line("[1,1,3,1,1]");
+
+        assert_eq!(a.cmp(&b), Greater);
+    }
+
+    #[test]
+    fn test_display_round_trips() {
+        let contents = fs::read_to_string("resources/2022/day13/test_input.txt").unwrap();
 
-        assert_eq!(sorted_pair(a, b), Greater);
+        for line in contents.lines().filter(|line| !line.is_empty()) {
+            assert_eq!(parse_line(line).to_string(), line);
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!("[1,2".parse::<Packet>(), Err(PacketError::UnexpectedEnd));
+        assert_eq!(
+            "[1;2]".parse::<Packet>(),
+            Err(PacketError::UnexpectedCharacter(';'))
+        );
+        assert_eq!("[1]]".parse::<Packet>(), Err(PacketError::TrailingInput));
+        assert_eq!(
+            "[10,[]]".parse::<Packet>(),
+            Ok(Packet::List(vec![Packet::Int(10), Packet::List(vec![])]))
+        );
     }
 }
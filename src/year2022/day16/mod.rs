@@ -1,11 +1,179 @@
+use std::collections::HashMap;
+use std::fs;
+
 pub mod task1 {
+    use super::max_pressure_release;
+
     pub fn ans() -> u128 {
-        0
+        max_pressure_release("resources/2022/day16/input")
     }
 }
 
 pub mod task2 {
+    use super::max_pressure_release_with_elephant;
+
     pub fn ans() -> u128 {
-        0
+        max_pressure_release_with_elephant("resources/2022/day16/input")
+    }
+}
+
+struct Valves {
+    // Flow rates and travel times between the start and every valve with a non zero flow.
+    // The start is always the last entry, and isn't part of the bitmasks
+    flow_rates: Vec<u128>,
+    distances: Vec<Vec<u128>>,
+    start: usize,
+}
+
+fn max_pressure_release(file: &str) -> u128 {
+    let valves = parse_file(file);
+
+    best_release_per_valve_set(&valves, 30)
+        .into_values()
+        .max()
+        .unwrap()
+}
+
+fn max_pressure_release_with_elephant(file: &str) -> u128 {
+    // Me and the elephant open disjoint sets of valves, so the best combined release is
+    // the best pair of disjoint sets, each opened by one of us in 26 minutes.
+    let valves = parse_file(file);
+    let valve_count = valves.flow_rates.len() - 1;
+    let all_valves = (1usize << valve_count) - 1;
+
+    let best_per_set = best_release_per_valve_set(&valves, 26);
+
+    // best_within[mask] is the best release opening any subset of the valves in mask
+    let mut best_within = vec![0; all_valves + 1];
+    for (mask, release) in best_per_set {
+        best_within[mask] = best_within[mask].max(release);
+    }
+    for valve in 0..valve_count {
+        for mask in 0..=all_valves {
+            if mask & (1 << valve) != 0 {
+                best_within[mask] = best_within[mask].max(best_within[mask ^ (1 << valve)]);
+            }
+        }
+    }
+
+    (0..=all_valves)
+        .map(|mask| best_within[mask] + best_within[all_valves ^ mask])
+        .max()
+        .unwrap()
+}
+
+fn best_release_per_valve_set(valves: &Valves, time: u128) -> HashMap<usize, u128> {
+    let mut best = HashMap::new();
+    visit_valves(valves, valves.start, time, 0, 0, &mut best);
+    best
+}
+
+fn visit_valves(
+    valves: &Valves,
+    position: usize,
+    time_left: u128,
+    opened: usize,
+    released: u128,
+    best: &mut HashMap<usize, u128>,
+) {
+    let entry = best.entry(opened).or_insert(0);
+    *entry = (*entry).max(released);
+
+    for next in 0..valves.start {
+        if opened & (1 << next) != 0 {
+            continue;
+        }
+
+        // Walking to the valve and opening it must leave at least a minute of flow
+        let time_taken = valves.distances[position][next] + 1;
+        if time_taken >= time_left {
+            continue;
+        }
+
+        let time_left = time_left - time_taken;
+        visit_valves(
+            valves,
+            next,
+            time_left,
+            opened | (1 << next),
+            released + time_left * valves.flow_rates[next],
+            best,
+        );
+    }
+}
+
+fn parse_file(file: &str) -> Valves {
+    let contents = fs::read_to_string(file).expect("Error reading file");
+
+    // Eg. "Valve BB has flow rate=13; tunnels lead to valves CC, AA"
+    let valves: Vec<(&str, u128, Vec<&str>)> = contents
+        .lines()
+        .map(|line| {
+            let name = &line[6..8];
+            let (rate, tunnels) = line.split_once("; ").unwrap();
+            let rate = rate.split('=').nth(1).unwrap().parse().unwrap();
+            let tunnels = tunnels.splitn(5, ' ').nth(4).unwrap().split(", ").collect();
+
+            (name, rate, tunnels)
+        })
+        .collect();
+
+    let indices: HashMap<&str, usize> = valves
+        .iter()
+        .enumerate()
+        .map(|(index, (name, _, _))| (*name, index))
+        .collect();
+
+    // Floyd-Warshall over every valve, before keeping only the ones worth visiting
+    let n = valves.len();
+    let mut all_distances = vec![vec![u128::MAX / 2; n]; n];
+    for (index, (_, _, tunnels)) in valves.iter().enumerate() {
+        all_distances[index][index] = 0;
+        for tunnel in tunnels {
+            all_distances[index][indices[tunnel]] = 1;
+        }
+    }
+    for k in 0..n {
+        for i in 0..n {
+            for j in 0..n {
+                let through_k = all_distances[i][k] + all_distances[k][j];
+                if through_k < all_distances[i][j] {
+                    all_distances[i][j] = through_k;
+                }
+            }
+        }
+    }
+
+    let mut kept: Vec<usize> = (0..n).filter(|index| valves[*index].1 > 0).collect();
+    kept.push(indices["AA"]);
+
+    Valves {
+        flow_rates: kept.iter().map(|index| valves[*index].1).collect(),
+        distances: kept
+            .iter()
+            .map(|from| kept.iter().map(|to| all_distances[*from][*to]).collect())
+            .collect(),
+        start: kept.len() - 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_max_pressure_release() {
+        assert_eq!(
+            max_pressure_release("resources/2022/day16/test_input"),
+            1651
+        );
+    }
+
+    #[test]
+    fn test_max_pressure_release_with_elephant() {
+        assert_eq!(
+            max_pressure_release_with_elephant("resources/2022/day16/test_input"),
+            1707
+        );
     }
 }
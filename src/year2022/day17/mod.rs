@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+use std::fs;
+
+pub mod task1 {
+    use super::tower_height;
+
+    pub fn ans() -> u128 {
+        tower_height("resources/2022/day17/input", 2022)
+    }
+}
+
+pub mod task2 {
+    use super::tower_height;
+
+    pub fn ans() -> u128 {
+        tower_height("resources/2022/day17/input", 1_000_000_000_000)
+    }
+}
+
+// Each rock is a list of rows from the bottom up, with bit x set for an occupied column x.
+// They start two columns in from the left wall
+const ROCKS: [&[u8]; 5] = [
+    &[0b0111100],
+    &[0b0001000, 0b0011100, 0b0001000],
+    &[0b0011100, 0b0010000, 0b0010000],
+    &[0b0000100, 0b0000100, 0b0000100, 0b0000100],
+    &[0b0001100, 0b0001100],
+];
+
+const RIGHT_WALL: u8 = 0b1000000;
+const LEFT_WALL: u8 = 0b0000001;
+
+// Enough rows of the top of the tower to tell two states apart
+const PROFILE_DEPTH: usize = 32;
+
+struct Chamber {
+    rows: Vec<u8>,
+    jets: Vec<char>,
+    jet_index: usize,
+    rock_count: u128,
+}
+
+impl Chamber {
+    fn new(jets: Vec<char>) -> Self {
+        Self {
+            rows: vec![],
+            jets,
+            jet_index: 0,
+            rock_count: 0,
+        }
+    }
+
+    fn collides(&self, rock: &[u8], height: usize) -> bool {
+        rock.iter().enumerate().any(|(i, row)| {
+            self.rows
+                .get(height + i)
+                .is_some_and(|filled| filled & row != 0)
+        })
+    }
+
+    fn drop_rock(&mut self) {
+        let mut rock = ROCKS[(self.rock_count % 5) as usize].to_vec();
+        let mut height = self.rows.len() + 3;
+
+        loop {
+            let jet = self.jets[self.jet_index];
+            self.jet_index = (self.jet_index + 1) % self.jets.len();
+
+            let pushed: Option<Vec<u8>> = match jet {
+                '<' if rock.iter().all(|row| row & LEFT_WALL == 0) => {
+                    Some(rock.iter().map(|row| row >> 1).collect())
+                }
+                '>' if rock.iter().all(|row| row & RIGHT_WALL == 0) => {
+                    Some(rock.iter().map(|row| row << 1).collect())
+                }
+                _ => None,
+            };
+            if let Some(pushed) = pushed {
+                if !self.collides(&pushed, height) {
+                    rock = pushed;
+                }
+            }
+
+            if height == 0 || self.collides(&rock, height - 1) {
+                break;
+            }
+            height -= 1;
+        }
+
+        for (i, row) in rock.into_iter().enumerate() {
+            if height + i >= self.rows.len() {
+                self.rows.push(0);
+            }
+            self.rows[height + i] |= row;
+        }
+
+        self.rock_count += 1;
+    }
+
+    fn state(&self) -> (u128, usize, Vec<u8>) {
+        let profile = self
+            .rows
+            .iter()
+            .rev()
+            .take(PROFILE_DEPTH)
+            .copied()
+            .collect();
+        (self.rock_count % 5, self.jet_index, profile)
+    }
+}
+
+fn tower_height(file: &str, rocks: u128) -> u128 {
+    // The jets and rocks both repeat, so eventually the top of the tower does too.
+    // Once a state repeats, whole cycles can be skipped by adding their height.
+    let contents = fs::read_to_string(file).expect("Error reading file");
+    let mut chamber = Chamber::new(contents.trim().chars().collect());
+
+    let mut seen: HashMap<(u128, usize, Vec<u8>), (u128, u128)> = HashMap::new();
+    let mut skipped_height = 0;
+
+    while chamber.rock_count < rocks {
+        chamber.drop_rock();
+
+        if skipped_height > 0 || chamber.rows.len() < PROFILE_DEPTH {
+            continue;
+        }
+
+        let height = chamber.rows.len() as u128;
+        if let Some((previous_count, previous_height)) =
+            seen.insert(chamber.state(), (chamber.rock_count, height))
+        {
+            let cycle_length = chamber.rock_count - previous_count;
+            let cycles = (rocks - chamber.rock_count) / cycle_length;
+
+            skipped_height = cycles * (height - previous_height);
+            chamber.rock_count += cycles * cycle_length;
+        }
+    }
+
+    chamber.rows.len() as u128 + skipped_height
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tower_height() {
+        assert_eq!(tower_height("resources/2022/day17/test_input", 2022), 3068);
+    }
+
+    #[test]
+    fn test_tower_height_with_cycles() {
+        assert_eq!(
+            tower_height("resources/2022/day17/test_input", 1_000_000_000_000),
+            1514285714288
+        );
+    }
+}
@@ -0,0 +1,171 @@
+use std::fs;
+
+pub mod task1 {
+    use super::sum_quality_levels;
+
+    pub fn ans() -> u128 {
+        sum_quality_levels("resources/2022/day19/input")
+    }
+}
+
+pub mod task2 {
+    use super::product_max_geodes;
+
+    pub fn ans() -> u128 {
+        product_max_geodes("resources/2022/day19/input")
+    }
+}
+
+const ORE: usize = 0;
+const CLAY: usize = 1;
+const OBSIDIAN: usize = 2;
+const GEODE: usize = 3;
+
+struct Blueprint {
+    id: u128,
+    // costs[robot][resource]
+    costs: [[u128; 3]; 4],
+}
+
+#[derive(Clone, Copy)]
+struct State {
+    time_left: u128,
+    robots: [u128; 4],
+    resources: [u128; 4],
+}
+
+fn sum_quality_levels(file: &str) -> u128 {
+    parse_file(file)
+        .iter()
+        .map(|blueprint| blueprint.id * max_geodes(blueprint, 24))
+        .sum()
+}
+
+fn product_max_geodes(file: &str) -> u128 {
+    parse_file(file)
+        .iter()
+        .take(3)
+        .map(|blueprint| max_geodes(blueprint, 32))
+        .product()
+}
+
+fn max_geodes(blueprint: &Blueprint, time: u128) -> u128 {
+    // Rather than simulating every minute, each branch picks the next robot to build and
+    // skips ahead to when it can be afforded. There's no point having more robots of a
+    // resource than can be spent in a minute, and branches that couldn't beat the best
+    // even building a geode robot every remaining minute are dropped.
+    let mut max_spend = [0; 3];
+    for costs in blueprint.costs {
+        for resource in 0..3 {
+            max_spend[resource] = max_spend[resource].max(costs[resource]);
+        }
+    }
+
+    let start = State {
+        time_left: time,
+        robots: [1, 0, 0, 0],
+        resources: [0; 4],
+    };
+
+    let mut best = 0;
+    search(blueprint, &max_spend, start, &mut best);
+    best
+}
+
+fn search(blueprint: &Blueprint, max_spend: &[u128; 3], state: State, best: &mut u128) {
+    let t = state.time_left;
+    let idle_geodes = state.resources[GEODE] + state.robots[GEODE] * t;
+    *best = (*best).max(idle_geodes);
+
+    if idle_geodes + t * t.saturating_sub(1) / 2 <= *best {
+        return;
+    }
+
+    for robot in [GEODE, OBSIDIAN, CLAY, ORE] {
+        if robot != GEODE && state.robots[robot] >= max_spend[robot] {
+            continue;
+        }
+
+        let costs = blueprint.costs[robot];
+
+        // Minutes of collecting before the robot can be afforded, or None if it never can be
+        let wait = (0..3)
+            .map(|resource| {
+                let missing = costs[resource].saturating_sub(state.resources[resource]);
+                match (missing, state.robots[resource]) {
+                    (0, _) => Some(0),
+                    (_, 0) => None,
+                    (missing, robots) => Some(missing.div_ceil(robots)),
+                }
+            })
+            .try_fold(0, |wait, resource_wait| Some(wait.max(resource_wait?)));
+
+        let Some(wait) = wait else {
+            continue;
+        };
+
+        // The robot needs a minute to build, and to be useful it must finish with time left
+        if wait + 1 >= t {
+            continue;
+        }
+
+        let mut next = state;
+        next.time_left = t - wait - 1;
+        for resource in 0..4 {
+            next.resources[resource] += state.robots[resource] * (wait + 1);
+        }
+        for (resource, cost) in next.resources.iter_mut().zip(costs) {
+            *resource -= cost;
+        }
+        next.robots[robot] += 1;
+
+        search(blueprint, max_spend, next, best);
+    }
+}
+
+fn parse_file(file: &str) -> Vec<Blueprint> {
+    let contents = fs::read_to_string(file).expect("Error reading file");
+
+    // Eg. "Blueprint 1: Each ore robot costs 4 ore. Each clay robot costs 2 ore.
+    // Each obsidian robot costs 3 ore and 14 clay. Each geode robot costs 2 ore and 7 obsidian."
+    // Only the numbers matter, and they're always in the same order
+    contents
+        .split("Blueprint")
+        .filter(|blueprint| !blueprint.trim().is_empty())
+        .map(|blueprint| {
+            let numbers: Vec<u128> = blueprint
+                .split(|c: char| !c.is_ascii_digit())
+                .filter(|number| !number.is_empty())
+                .map(|number| number.parse().unwrap())
+                .collect();
+
+            Blueprint {
+                id: numbers[0],
+                costs: [
+                    [numbers[1], 0, 0],
+                    [numbers[2], 0, 0],
+                    [numbers[3], numbers[4], 0],
+                    [numbers[5], 0, numbers[6]],
+                ],
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sum_quality_levels() {
+        assert_eq!(sum_quality_levels("resources/2022/day19/test_input"), 33);
+    }
+
+    #[test]
+    fn test_max_geodes_in_32_minutes() {
+        let blueprints = parse_file("resources/2022/day19/test_input");
+
+        assert_eq!(max_geodes(&blueprints[0], 32), 56);
+        assert_eq!(max_geodes(&blueprints[1], 32), 62);
+    }
+}
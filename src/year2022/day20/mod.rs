@@ -0,0 +1,74 @@
+use std::fs;
+
+pub mod task1 {
+    use super::grove_coordinates;
+
+    pub fn ans() -> i128 {
+        grove_coordinates("resources/2022/day20/input", 1, 1)
+    }
+}
+
+pub mod task2 {
+    use super::grove_coordinates;
+
+    pub fn ans() -> i128 {
+        grove_coordinates("resources/2022/day20/input", 811589153, 10)
+    }
+}
+
+fn grove_coordinates(file: &str, key: i128, rounds: usize) -> i128 {
+    let numbers: Vec<i128> = fs::read_to_string(file)
+        .expect("Error reading file")
+        .lines()
+        .map(|line| line.parse::<i128>().unwrap() * key)
+        .collect();
+
+    let mixed = mix(&numbers, rounds);
+
+    let zero = mixed.iter().position(|number| *number == 0).unwrap();
+    [1000, 2000, 3000]
+        .iter()
+        .map(|offset| mixed[(zero + offset) % mixed.len()])
+        .sum()
+}
+
+fn mix(numbers: &[i128], rounds: usize) -> Vec<i128> {
+    // Numbers can repeat, so the order is tracked by original index rather than by value
+    let mut order: Vec<usize> = (0..numbers.len()).collect();
+
+    // A number moving a whole lap of the other numbers ends up where it started
+    let lap = numbers.len() as i128 - 1;
+
+    for _ in 0..rounds {
+        for (index, number) in numbers.iter().enumerate() {
+            let position = order.iter().position(|i| *i == index).unwrap();
+            order.remove(position);
+
+            let new_position = (position as i128 + number).rem_euclid(lap) as usize;
+            order.insert(new_position, index);
+        }
+    }
+
+    order.into_iter().map(|index| numbers[index]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grove_coordinates() {
+        assert_eq!(
+            grove_coordinates("resources/2022/day20/test_input", 1, 1),
+            3
+        );
+    }
+
+    #[test]
+    fn test_grove_coordinates_with_key() {
+        assert_eq!(
+            grove_coordinates("resources/2022/day20/test_input", 811589153, 10),
+            1623178306
+        );
+    }
+}
@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::fs;
+
+pub mod task1 {
+    use super::root_number;
+
+    pub fn ans() -> i128 {
+        root_number("resources/2022/day21/input")
+    }
+}
+
+pub mod task2 {
+    use super::humn_number;
+
+    pub fn ans() -> i128 {
+        humn_number("resources/2022/day21/input")
+    }
+}
+
+enum Job {
+    Number(i128),
+    Operation(String, char, String),
+}
+
+type Monkeys = HashMap<String, Job>;
+
+fn root_number(file: &str) -> i128 {
+    let monkeys = parse_file(file);
+
+    evaluate(&monkeys, "root")
+}
+
+fn humn_number(file: &str) -> i128 {
+    // humn only appears once in the tree, so one side of root is a fixed number.
+    // Working down from root, each operation can be undone to find what the side
+    // holding humn must equal, until humn itself is reached.
+    let monkeys = parse_file(file);
+
+    let Job::Operation(left, _, right) = &monkeys["root"] else {
+        panic!("root must be an operation");
+    };
+
+    if depends_on_humn(&monkeys, left) {
+        solve_for_humn(&monkeys, left, evaluate(&monkeys, right))
+    } else {
+        solve_for_humn(&monkeys, right, evaluate(&monkeys, left))
+    }
+}
+
+fn evaluate(monkeys: &Monkeys, name: &str) -> i128 {
+    match &monkeys[name] {
+        Job::Number(number) => *number,
+        Job::Operation(left, operation, right) => {
+            let left = evaluate(monkeys, left);
+            let right = evaluate(monkeys, right);
+
+            match operation {
+                '+' => left + right,
+                '-' => left - right,
+                '*' => left * right,
+                '/' => left / right,
+                _ => panic!("Invalid operation"),
+            }
+        }
+    }
+}
+
+fn depends_on_humn(monkeys: &Monkeys, name: &str) -> bool {
+    if name == "humn" {
+        return true;
+    }
+
+    match &monkeys[name] {
+        Job::Number(_) => false,
+        Job::Operation(left, _, right) => {
+            depends_on_humn(monkeys, left) || depends_on_humn(monkeys, right)
+        }
+    }
+}
+
+fn solve_for_humn(monkeys: &Monkeys, name: &str, target: i128) -> i128 {
+    if name == "humn" {
+        return target;
+    }
+
+    let Job::Operation(left, operation, right) = &monkeys[name] else {
+        panic!("{name} doesn't depend on humn");
+    };
+
+    if depends_on_humn(monkeys, left) {
+        // target = left op known
+        let known = evaluate(monkeys, right);
+        let left_target = match operation {
+            '+' => target - known,
+            '-' => target + known,
+            '*' => target / known,
+            '/' => target * known,
+            _ => panic!("Invalid operation"),
+        };
+
+        solve_for_humn(monkeys, left, left_target)
+    } else {
+        // target = known op right
+        let known = evaluate(monkeys, left);
+        let right_target = match operation {
+            '+' => target - known,
+            '-' => known - target,
+            '*' => target / known,
+            '/' => known / target,
+            _ => panic!("Invalid operation"),
+        };
+
+        solve_for_humn(monkeys, right, right_target)
+    }
+}
+
+fn parse_file(file: &str) -> Monkeys {
+    let contents = fs::read_to_string(file).expect("Error reading file");
+
+    contents
+        .lines()
+        .map(|line| {
+            let (name, job) = line.split_once(": ").unwrap();
+
+            let job = match job.split(' ').collect::<Vec<&str>>()[..] {
+                [number] => Job::Number(number.parse().unwrap()),
+                [left, operation, right] => Job::Operation(
+                    left.to_string(),
+                    operation.chars().next().unwrap(),
+                    right.to_string(),
+                ),
+                _ => panic!("Invalid job {job}"),
+            };
+
+            (name.to_string(), job)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_root_number() {
+        assert_eq!(root_number("resources/2022/day21/test_input"), 152);
+    }
+
+    #[test]
+    fn test_humn_number() {
+        assert_eq!(humn_number("resources/2022/day21/test_input"), 301);
+    }
+}
@@ -0,0 +1,332 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+
+pub mod task1 {
+    use super::final_password;
+
+    pub fn ans() -> u128 {
+        final_password("resources/2022/day22/input", false)
+    }
+}
+
+pub mod task2 {
+    use super::final_password;
+
+    pub fn ans() -> u128 {
+        final_password("resources/2022/day22/input", true)
+    }
+}
+
+enum Move {
+    Forward(usize),
+    Left,
+    Right,
+}
+
+// Facing 0 is right, 1 down, 2 left, 3 up, matching the password scoring
+const STEPS: [(isize, isize); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+
+type Vector = [isize; 3];
+
+#[derive(Clone, Copy)]
+struct Face {
+    // Outward normal, and the directions of the net's x and y axes once folded
+    normal: Vector,
+    right: Vector,
+    down: Vector,
+}
+
+struct Board {
+    tiles: Vec<Vec<char>>,
+    moves: Vec<Move>,
+}
+
+impl Board {
+    fn tile(&self, x: isize, y: isize) -> char {
+        if x < 0 || y < 0 {
+            return ' ';
+        }
+        *self
+            .tiles
+            .get(y as usize)
+            .and_then(|row| row.get(x as usize))
+            .unwrap_or(&' ')
+    }
+}
+
+fn final_password(file: &str, as_cube: bool) -> u128 {
+    let board = parse_file(file);
+
+    let (x, y, facing) = if as_cube {
+        let cube = fold_cube(&board);
+        walk(&board, |x, y, facing| wrap_cube(&cube, x, y, facing))
+    } else {
+        walk(&board, |x, y, facing| wrap_flat(&board, x, y, facing))
+    };
+
+    (1000 * (y + 1) + 4 * (x + 1) + facing as isize) as u128
+}
+
+fn walk(
+    board: &Board,
+    wrap: impl Fn(isize, isize, usize) -> (isize, isize, usize),
+) -> (isize, isize, usize) {
+    let mut x = board.tiles[0].iter().position(|tile| *tile == '.').unwrap() as isize;
+    let mut y = 0;
+    let mut facing = 0;
+
+    for next_move in &board.moves {
+        match next_move {
+            Move::Left => facing = (facing + 3) % 4,
+            Move::Right => facing = (facing + 1) % 4,
+            Move::Forward(steps) => {
+                for _ in 0..*steps {
+                    let (dx, dy) = STEPS[facing];
+                    let mut next = (x + dx, y + dy, facing);
+                    if board.tile(next.0, next.1) == ' ' {
+                        next = wrap(x, y, facing);
+                    }
+
+                    if board.tile(next.0, next.1) == '#' {
+                        break;
+                    }
+                    (x, y, facing) = next;
+                }
+            }
+        }
+    }
+
+    (x, y, facing)
+}
+
+fn wrap_flat(board: &Board, x: isize, y: isize, facing: usize) -> (isize, isize, usize) {
+    // Walk backwards to the far edge of the board
+    let (dx, dy) = STEPS[facing];
+    let (mut x, mut y) = (x, y);
+    while board.tile(x - dx, y - dy) != ' ' {
+        x -= dx;
+        y -= dy;
+    }
+
+    (x, y, facing)
+}
+
+struct Cube {
+    size: isize,
+    // Faces keyed by their position on the net, in units of the face size
+    faces: HashMap<(isize, isize), Face>,
+}
+
+fn fold_cube(board: &Board) -> Cube {
+    let tile_count = board
+        .tiles
+        .iter()
+        .flatten()
+        .filter(|tile| **tile != ' ')
+        .count();
+    let size = ((tile_count / 6) as f64).sqrt() as isize;
+
+    let first_x = board.tiles[0].iter().position(|tile| *tile != ' ').unwrap() as isize;
+    let start = (first_x / size, 0);
+
+    // Folding each face onto its neighbour on the net turns the neighbour's normal
+    // into the direction of the shared edge, and the old normal into the way back
+    let mut faces = HashMap::new();
+    faces.insert(
+        start,
+        Face {
+            normal: [0, 0, -1],
+            right: [1, 0, 0],
+            down: [0, 1, 0],
+        },
+    );
+
+    let mut queue = VecDeque::from([start]);
+    while let Some((fx, fy)) = queue.pop_front() {
+        let face = faces[&(fx, fy)];
+
+        let neighbours = [
+            (
+                (fx + 1, fy),
+                Face {
+                    normal: face.right,
+                    right: neg(face.normal),
+                    down: face.down,
+                },
+            ),
+            (
+                (fx - 1, fy),
+                Face {
+                    normal: neg(face.right),
+                    right: face.normal,
+                    down: face.down,
+                },
+            ),
+            (
+                (fx, fy + 1),
+                Face {
+                    normal: face.down,
+                    right: face.right,
+                    down: neg(face.normal),
+                },
+            ),
+            (
+                (fx, fy - 1),
+                Face {
+                    normal: neg(face.down),
+                    right: face.right,
+                    down: face.normal,
+                },
+            ),
+        ];
+
+        for ((nx, ny), neighbour) in neighbours {
+            if faces.contains_key(&(nx, ny)) || board.tile(nx * size, ny * size) == ' ' {
+                continue;
+            }
+
+            faces.insert((nx, ny), neighbour);
+            queue.push_back((nx, ny));
+        }
+    }
+
+    Cube { size, faces }
+}
+
+fn wrap_cube(cube: &Cube, x: isize, y: isize, facing: usize) -> (isize, isize, usize) {
+    // Positions are measured from the cube's centre in half tiles, so tile centres are
+    // odd numbers and each face sits at +/- size along its normal
+    let s = cube.size;
+    let face_position = (x / s, y / s);
+    let face = cube.faces[&face_position];
+
+    let (local_x, local_y) = (x - face_position.0 * s, y - face_position.1 * s);
+    let position = add(
+        scale(face.normal, s),
+        add(
+            scale(face.right, 2 * local_x + 1 - s),
+            scale(face.down, 2 * local_y + 1 - s),
+        ),
+    );
+
+    let direction = directions(&face)[facing];
+
+    // Over the edge, the tile moves half a tile out along the direction of travel and
+    // half a tile in along the old normal, which puts it on the next face
+    let position = add(add(position, direction), neg(face.normal));
+
+    let (&(next_fx, next_fy), next_face) = cube
+        .faces
+        .iter()
+        .find(|(_, next_face)| next_face.normal == direction)
+        .unwrap();
+
+    let next_x = (dot(position, next_face.right) + s - 1) / 2;
+    let next_y = (dot(position, next_face.down) + s - 1) / 2;
+    let next_facing = directions(next_face)
+        .iter()
+        .position(|next_direction| *next_direction == neg(face.normal))
+        .unwrap();
+
+    (next_fx * s + next_x, next_fy * s + next_y, next_facing)
+}
+
+fn directions(face: &Face) -> [Vector; 4] {
+    [face.right, face.down, neg(face.right), neg(face.down)]
+}
+
+fn neg(a: Vector) -> Vector {
+    [-a[0], -a[1], -a[2]]
+}
+
+fn add(a: Vector, b: Vector) -> Vector {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn scale(a: Vector, factor: isize) -> Vector {
+    [a[0] * factor, a[1] * factor, a[2] * factor]
+}
+
+fn dot(a: Vector, b: Vector) -> isize {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn parse_file(file: &str) -> Board {
+    let contents = fs::read_to_string(file).expect("Error reading file");
+    let (map, path) = contents.split_once("\n\n").unwrap();
+
+    let tiles = map.lines().map(|line| line.chars().collect()).collect();
+
+    let mut moves = vec![];
+    let mut steps = String::new();
+    for c in path.trim().chars() {
+        if c.is_ascii_digit() {
+            steps.push(c);
+            continue;
+        }
+
+        if !steps.is_empty() {
+            moves.push(Move::Forward(steps.parse().unwrap()));
+            steps.clear();
+        }
+        moves.push(match c {
+            'L' => Move::Left,
+            'R' => Move::Right,
+            _ => panic!("Invalid move {c}"),
+        });
+    }
+    if !steps.is_empty() {
+        moves.push(Move::Forward(steps.parse().unwrap()));
+    }
+
+    Board { tiles, moves }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_final_password() {
+        assert_eq!(
+            final_password("resources/2022/day22/test_input", false),
+            6032
+        );
+    }
+
+    #[test]
+    fn test_final_password_on_cube() {
+        assert_eq!(
+            final_password("resources/2022/day22/test_input", true),
+            5031
+        );
+    }
+
+    #[test]
+    fn test_cube_laps_return_to_start() {
+        // A differently shaped net, with no walls walking a lap of the cube in any
+        // direction should end up back where it started
+        let net = [
+            "  ....", "  ....", "  ..", "  ..", "....", "....", "..", "..",
+        ];
+        let board = Board {
+            tiles: net.iter().map(|line| line.chars().collect()).collect(),
+            moves: vec![],
+        };
+        let cube = fold_cube(&board);
+
+        for start_facing in 0..4 {
+            let (mut x, mut y, mut facing) = (2, 0, start_facing);
+            for _ in 0..8 {
+                let (dx, dy) = STEPS[facing];
+                (x, y, facing) = if board.tile(x + dx, y + dy) == ' ' {
+                    wrap_cube(&cube, x, y, facing)
+                } else {
+                    (x + dx, y + dy, facing)
+                };
+                assert_ne!(board.tile(x, y), ' ');
+            }
+            assert_eq!((x, y, facing), (2, 0, start_facing));
+        }
+    }
+}
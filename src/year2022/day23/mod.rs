@@ -0,0 +1,126 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+
+pub mod task1 {
+    use super::empty_ground_tiles;
+
+    pub fn ans() -> u128 {
+        empty_ground_tiles("resources/2022/day23/input", 10)
+    }
+}
+
+pub mod task2 {
+    use super::first_still_round;
+
+    pub fn ans() -> u128 {
+        first_still_round("resources/2022/day23/input")
+    }
+}
+
+type Elves = HashSet<(isize, isize)>;
+
+// The three tiles to check, and the step to take if they're all empty
+type Proposal = ([(isize, isize); 3], (isize, isize));
+
+// North, south, west, then east
+const PROPOSALS: [Proposal; 4] = [
+    ([(-1, -1), (0, -1), (1, -1)], (0, -1)),
+    ([(-1, 1), (0, 1), (1, 1)], (0, 1)),
+    ([(-1, -1), (-1, 0), (-1, 1)], (-1, 0)),
+    ([(1, -1), (1, 0), (1, 1)], (1, 0)),
+];
+
+fn empty_ground_tiles(file: &str, rounds: usize) -> u128 {
+    let mut elves = parse_file(file);
+
+    for round in 0..rounds {
+        run_round(&mut elves, round);
+    }
+
+    let min_x = elves.iter().map(|(x, _)| x).min().unwrap();
+    let max_x = elves.iter().map(|(x, _)| x).max().unwrap();
+    let min_y = elves.iter().map(|(_, y)| y).min().unwrap();
+    let max_y = elves.iter().map(|(_, y)| y).max().unwrap();
+
+    ((max_x - min_x + 1) * (max_y - min_y + 1)) as u128 - elves.len() as u128
+}
+
+fn first_still_round(file: &str) -> u128 {
+    let mut elves = parse_file(file);
+
+    let mut round = 0;
+    while run_round(&mut elves, round) {
+        round += 1;
+    }
+
+    round as u128 + 1
+}
+
+// Returns whether any elf moved
+fn run_round(elves: &mut Elves, round: usize) -> bool {
+    let mut proposals: HashMap<(isize, isize), Vec<(isize, isize)>> = HashMap::new();
+
+    for &(x, y) in elves.iter() {
+        let occupied = |(dx, dy): &(isize, isize)| elves.contains(&(x + dx, y + dy));
+
+        let alone = PROPOSALS
+            .iter()
+            .all(|(checks, _)| !checks.iter().any(occupied));
+        if alone {
+            continue;
+        }
+
+        let proposal = (0..4)
+            .map(|i| PROPOSALS[(round + i) % 4])
+            .find(|(checks, _)| !checks.iter().any(occupied));
+
+        if let Some((_, (dx, dy))) = proposal {
+            proposals.entry((x + dx, y + dy)).or_default().push((x, y));
+        }
+    }
+
+    let mut moved = false;
+    for (target, proposers) in proposals {
+        // Elves proposing the same tile all stay put
+        if let [elf] = proposers[..] {
+            elves.remove(&elf);
+            elves.insert(target);
+            moved = true;
+        }
+    }
+
+    moved
+}
+
+fn parse_file(file: &str) -> Elves {
+    let contents = fs::read_to_string(file).expect("Error reading file");
+
+    contents
+        .lines()
+        .enumerate()
+        .flat_map(|(y, line)| {
+            line.chars()
+                .enumerate()
+                .filter(|(_, c)| *c == '#')
+                .map(move |(x, _)| (x as isize, y as isize))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_ground_tiles() {
+        assert_eq!(
+            empty_ground_tiles("resources/2022/day23/test_input", 10),
+            110
+        );
+    }
+
+    #[test]
+    fn test_first_still_round() {
+        assert_eq!(first_still_round("resources/2022/day23/test_input"), 20);
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::HashSet;
+use std::fs;
+
+pub mod task1 {
+    use super::fewest_minutes;
+
+    pub fn ans() -> u128 {
+        fewest_minutes("resources/2022/day24/input", 1)
+    }
+}
+
+pub mod task2 {
+    use super::fewest_minutes;
+
+    pub fn ans() -> u128 {
+        fewest_minutes("resources/2022/day24/input", 3)
+    }
+}
+
+struct Valley {
+    // The blizzards' starting tiles, without the surrounding walls
+    blizzards: Vec<Vec<char>>,
+    width: isize,
+    height: isize,
+}
+
+impl Valley {
+    fn is_clear(&self, x: isize, y: isize, time: isize) -> bool {
+        // The start and end are in the wall, where blizzards never reach
+        if (x, y) == self.start() || (x, y) == self.end() {
+            return true;
+        }
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return false;
+        }
+
+        // Blizzards wrap around, so rather than moving them, look back along each
+        // direction to where a blizzard reaching this tile now would have started
+        let (w, h) = (self.width, self.height);
+        let row = &self.blizzards[y as usize];
+        let blizzard = |bx: isize, by: isize| self.blizzards[by as usize][bx as usize];
+
+        row[(x - time).rem_euclid(w) as usize] != '>'
+            && row[(x + time).rem_euclid(w) as usize] != '<'
+            && blizzard(x, (y - time).rem_euclid(h)) != 'v'
+            && blizzard(x, (y + time).rem_euclid(h)) != '^'
+    }
+
+    fn start(&self) -> (isize, isize) {
+        (0, -1)
+    }
+
+    fn end(&self) -> (isize, isize) {
+        (self.width - 1, self.height)
+    }
+
+    fn crossing_time(&self, from: (isize, isize), to: (isize, isize), start_time: isize) -> isize {
+        // Breadth first over every tile the expedition could be on each minute
+        let mut positions = HashSet::from([from]);
+        let mut time = start_time;
+
+        while !positions.contains(&to) {
+            time += 1;
+            positions = positions
+                .into_iter()
+                .flat_map(|(x, y)| [(x, y), (x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)])
+                .filter(|(x, y)| self.is_clear(*x, *y, time))
+                .collect();
+        }
+
+        time
+    }
+}
+
+fn fewest_minutes(file: &str, crossings: usize) -> u128 {
+    let valley = parse_file(file);
+
+    let mut time = 0;
+    for crossing in 0..crossings {
+        let (from, to) = if crossing % 2 == 0 {
+            (valley.start(), valley.end())
+        } else {
+            (valley.end(), valley.start())
+        };
+
+        time = valley.crossing_time(from, to, time);
+    }
+
+    time as u128
+}
+
+fn parse_file(file: &str) -> Valley {
+    let contents = fs::read_to_string(file).expect("Error reading file");
+    let lines: Vec<&str> = contents.lines().collect();
+
+    let blizzards: Vec<Vec<char>> = lines[1..lines.len() - 1]
+        .iter()
+        .map(|line| line.chars().skip(1).take(line.len() - 2).collect())
+        .collect();
+
+    Valley {
+        width: blizzards[0].len() as isize,
+        height: blizzards.len() as isize,
+        blizzards,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fewest_minutes() {
+        assert_eq!(fewest_minutes("resources/2022/day24/test_input", 1), 18);
+    }
+
+    #[test]
+    fn test_fewest_minutes_for_snacks() {
+        assert_eq!(fewest_minutes("resources/2022/day24/test_input", 3), 54);
+    }
+}
@@ -0,0 +1,93 @@
+use std::fs;
+
+pub mod task1 {
+    use super::sum_snafu_numbers;
+
+    pub fn ans() -> String {
+        sum_snafu_numbers("resources/2022/day25/input")
+    }
+}
+
+pub mod task2 {
+    pub fn ans() -> u128 {
+        // Start the blender:
+        0
+    }
+}
+
+fn sum_snafu_numbers(file: &str) -> String {
+    let total = fs::read_to_string(file)
+        .expect("Error reading file")
+        .lines()
+        .map(from_snafu)
+        .sum();
+
+    to_snafu(total)
+}
+
+fn from_snafu(snafu: &str) -> i128 {
+    snafu.chars().fold(0, |total, digit| {
+        let value = match digit {
+            '2' => 2,
+            '1' => 1,
+            '0' => 0,
+            '-' => -1,
+            '=' => -2,
+            _ => panic!("Invalid SNAFU digit {digit}"),
+        };
+
+        total * 5 + value
+    })
+}
+
+fn to_snafu(mut number: i128) -> String {
+    if number == 0 {
+        return "0".to_string();
+    }
+
+    // Digits 3 and 4 become -2 and -1, carrying one into the next place
+    let mut digits = vec![];
+    while number != 0 {
+        let (digit, carry) = match number.rem_euclid(5) {
+            0 => ('0', 0),
+            1 => ('1', 0),
+            2 => ('2', 0),
+            3 => ('=', 1),
+            _ => ('-', 1),
+        };
+
+        digits.push(digit);
+        number = number.div_euclid(5) + carry;
+    }
+
+    digits.into_iter().rev().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snafu_conversion() {
+        let cases = [
+            (1, "1"),
+            (3, "1="),
+            (2022, "1=11-2"),
+            (12345, "1-0---0"),
+            (314159265, "1121-1110-1=0"),
+        ];
+
+        for (number, snafu) in cases {
+            assert_eq!(to_snafu(number), snafu);
+            assert_eq!(from_snafu(snafu), number);
+        }
+    }
+
+    #[test]
+    fn test_sum_snafu_numbers() {
+        assert_eq!(
+            sum_snafu_numbers("resources/2022/day25/test_input"),
+            "2=-1=0"
+        );
+    }
+}
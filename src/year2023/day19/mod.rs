@@ -1,9 +1,10 @@
 use std::{
     cmp::{max, min},
-    collections::HashMap,
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    io::Write,
 };
 
-use itertools::Itertools;
 use std::ops::RangeInclusive;
 
 pub mod task1 {
@@ -23,316 +24,428 @@ pub mod task2 {
 }
 
 fn accepted_part_ranges(file: &str) -> u128 {
-    let (workflows, _) = parse_file(file);
-
-    let start_ranges: [RatingValueRange; 4] = [(1..=4000), (1..=4000), (1..=4000), (1..=4000)];
-    let mut part_ranges = vec![PartRange {
-        ranges: start_ranges,
-        next_workflow: "in".to_string(),
-    }];
-
-    let mut accepted_ranges = vec![];
-
-    while let Some(part_range) = part_ranges.pop() {
-        let workflow = workflows.get(&part_range.next_workflow).unwrap();
-
-        let mut ranges = part_range.ranges;
-
-        for step in workflow {
-            if let Step::Else(next_workflow) = step {
-                if next_workflow == "R" {
-                    break;
-                } else if next_workflow == "A" {
-                    accepted_ranges.push(ranges);
-                    break;
-                } else {
-                    part_ranges.push(PartRange {
-                        ranges: ranges.clone(),
-                        next_workflow: next_workflow.to_string(),
-                    });
-                    continue;
-                }
-            } else if let Step::Conditonal(operation, next_workflow) = step {
-                let mut branch_ranges = ranges.clone();
-
-                match operation {
-                    Operation::GreaterThan(rating, value) => {
-                        let branch_lower_bound = max(*value + 1, *ranges[*rating].start());
-                        let branch_upper_bound = *ranges[*rating].end();
-
-                        let main_lower_bound = *ranges[*rating].start();
-                        let main_upper_bound = min(*value, *ranges[*rating].end());
-
-                        branch_ranges[*rating] = branch_lower_bound..=branch_upper_bound;
-                        ranges[*rating] = main_lower_bound..=main_upper_bound;
-
-                        if branch_ranges[*rating].start() <= branch_ranges[*rating].end() {
-                            if next_workflow == "A" {
-                                accepted_ranges.push(branch_ranges);
-                            } else if next_workflow != "R" {
-                                part_ranges.push(PartRange {
-                                    ranges: branch_ranges,
-                                    next_workflow: next_workflow.to_string(),
-                                });
-                            }
-                        }
+    let (workflows, parts) = parse_file(file);
 
-                        if ranges[*rating].start() > ranges[*rating].end() {
-                            break;
-                        }
-                    }
-                    Operation::LessThan(rating, value) => {
-                        let branch_lower_bound = *ranges[*rating].start();
-                        let branch_upper_bound = min(*value - 1, *ranges[*rating].end());
-
-                        let main_lower_bound = max(*value, *ranges[*rating].start());
-                        let main_upper_bound = *ranges[*rating].end();
-
-                        branch_ranges[*rating] = branch_lower_bound..=branch_upper_bound;
-                        ranges[*rating] = main_lower_bound..=main_upper_bound;
-
-                        if branch_ranges[*rating].start() <= branch_ranges[*rating].end() {
-                            if next_workflow == "A" {
-                                accepted_ranges.push(branch_ranges);
-                            } else if next_workflow != "R" {
-                                part_ranges.push(PartRange {
-                                    ranges: branch_ranges,
-                                    next_workflow: next_workflow.to_string(),
-                                });
-                            }
-                        }
+    let compiled = CompiledWorkflows::compile(&workflows, &rating_names(&parts))
+        .unwrap_or_else(|error| panic!("{error}"));
 
-                        if ranges[*rating].start() > ranges[*rating].end() {
-                            break;
-                        }
-                    }
-                }
-            } else {
-                panic!("Bruh");
-            }
-        }
-    }
-    
-    reduce_ranges(accepted_ranges).into_iter().map(|range| {
-        range.into_iter().map(|range| {
-            (range.end() - range.start() + 1) as u128
-        }).product::<u128>()
-    }).sum::<u128>()
+    compiled.accepted_volume(&compiled.uniform_bounds(1..=4000))
 }
 
-fn reduce_ranges(mut ranges: Vec<PartRanges>) -> Vec<PartRanges> {
-    // Removes nested ranges, then performs a plane sweep over each axis to break up overlapping ranges
-    // then recurse on the new set of RangeInclusive
-    ranges = remove_duplicate_ranges(ranges);
-    ranges = remove_nested_ranges(ranges);
+fn accepted_parts(file: &str) -> u128 {
+    let (workflows, parts) = parse_file(file);
 
-    ranges.sort_by(sort_ranges);
+    let compiled = CompiledWorkflows::compile(&workflows, &rating_names(&parts))
+        .unwrap_or_else(|error| panic!("{error}"));
 
-    // Find a pair of ranges that overlap
-    for j in 0..ranges.len() - 1 {
-        for i in j + 1..ranges.len() {
-            if let Some(index) = do_cuboids_overlap(&ranges[j], &ranges[i]) {
-                let mut range_a = ranges.remove(j);
-                let mut range_b = ranges.remove(i - 1);
+    parts
+        .into_iter()
+        .filter(|part| {
+            let path = compiled
+                .workflow_path(part)
+                .unwrap_or_else(|error| panic!("{error}"));
+            path.last().unwrap() == "A"
+        })
+        .map(|part| part.ratings.iter().map(|(_, value)| value).sum::<usize>() as u128)
+        .sum::<u128>()
+}
 
-                if range_a[index].start() > range_b[index].start() {
-                    std::mem::swap(&mut range_a, &mut range_b);
-                }
+pub fn analyse_workflows(file: &str, mut output: impl Write) {
+    let (workflows, parts) = parse_file(file);
 
-                if range_b[index].end() > range_a[index].end() {
-                    // a and b partially overlap in this axis
-                    let new_range_start = *range_b[index].start();
-                    let new_range_end = *range_a[index].end();
+    let compiled = match CompiledWorkflows::compile(&workflows, &rating_names(&parts)) {
+        Ok(compiled) => compiled,
+        Err(error) => {
+            writeln!(output, "Error: {error}").unwrap();
+            return;
+        }
+    };
+    let bounds = compiled.uniform_bounds(1..=4000);
 
-                    range_a[index] = *range_a[index].start()..=new_range_start - 1;
-                    range_b[index] = new_range_end + 1..=*range_b[index].end();
+    for (workflow, index) in compiled.unreachable_rules(&bounds) {
+        writeln!(output, "Unreachable: {workflow} rule {}", index + 1).unwrap();
+    }
 
-                    let new_range = new_range_start..=new_range_end;
+    for part in &parts {
+        match compiled.workflow_path(part) {
+            Ok(path) => writeln!(output, "{part}: {}", path.join(" -> ")).unwrap(),
+            Err(error) => writeln!(output, "{part}: {error}").unwrap(),
+        }
+    }
 
-                    let mut a_right = range_a.clone();
-                    let mut b_left = range_b.clone();
+    writeln!(
+        output,
+        "Accepted volume: {}",
+        compiled.accepted_volume(&bounds)
+    )
+    .unwrap();
+}
 
-                    a_right[index] = new_range.clone();
-                    b_left[index] = new_range.clone();
+fn rating_names(parts: &Parts) -> Vec<RatingName> {
+    parts
+        .first()
+        .map(|part| part.ratings.iter().map(|(name, _)| name.clone()).collect())
+        .unwrap_or_default()
+}
 
-                    ranges.push(range_a);
-                    ranges.push(a_right);
-                    ranges.push(b_left);
-                    ranges.push(range_b);
-                } else {
-                    // b is contained within a in this axis
+// The workflows are compiled into a DAG, with a node for each rule. Conditional rules split the
+// parts on one rating, so following every branch from "in" breaks the bounds into disjoint regions
+type NodeId = usize;
+type Rule = (WorkflowName, usize);
 
-                    let new_range_start = *range_b[index].start();
-                    let new_range_end = *range_b[index].end();
+const ACCEPT: NodeId = 0;
+const REJECT: NodeId = 1;
 
-                    let mut a_right = range_a.clone();
-                    a_right[index] = new_range_end + 1..=*range_a[index].end();
-                    let mut a_middle = range_a.clone();
-                    a_middle[index] = new_range_start..=new_range_end;
-                    
-                    range_a[index] = *range_a[index].start()..=new_range_start - 1;
+#[derive(Debug)]
+enum Node {
+    Accept,
+    Reject,
+    Split {
+        rule: Rule,
+        rating: RatingIndex,
+        comparison: Comparison,
+        pass: NodeId,
+        fail: NodeId,
+    },
+    Jump {
+        rule: Rule,
+        next: NodeId,
+    },
+}
 
-                    ranges.push(range_a);
-                    ranges.push(a_middle);
-                    ranges.push(a_right);
-                    ranges.push(range_b);
-                }
-                return reduce_ranges(ranges);
-            }
+#[derive(PartialEq, Debug, Clone, Copy)]
+enum Comparison {
+    LessThan(RatingValue),
+    GreaterThan(RatingValue),
+}
+
+impl Comparison {
+    fn passes(&self, value: RatingValue) -> bool {
+        match self {
+            Comparison::LessThan(threshold) => value < *threshold,
+            Comparison::GreaterThan(threshold) => value > *threshold,
         }
     }
 
-    ranges
-}
-
-fn do_cuboids_overlap(a: &PartRanges, b: &PartRanges) -> Option<RatingIndex> {
-    if !(0..4).all(|i| do_ranges_overlap(&a[i], &b[i])) {
-        return None;
+    // Splits a range into the values passing and failing the comparison
+    fn split(
+        &self,
+        range: &RatingValueRange,
+    ) -> (Option<RatingValueRange>, Option<RatingValueRange>) {
+        let (start, end) = (*range.start(), *range.end());
+
+        match *self {
+            Comparison::LessThan(threshold) => (
+                (start < threshold).then(|| start..=min(end, threshold - 1)),
+                (end >= threshold).then(|| max(start, threshold)..=end),
+            ),
+            Comparison::GreaterThan(threshold) => (
+                (end > threshold).then(|| max(start, threshold + 1)..=end),
+                (start <= threshold).then(|| start..=min(end, threshold)),
+            ),
+        }
     }
-    (0..4)
-        .filter(|i| a[*i] != b[*i])
-        .find(|i| do_ranges_overlap(&a[*i], &b[*i]))
 }
 
-fn do_ranges_overlap(a: &RangeInclusive<RatingValue>, b: &RangeInclusive<RatingValue>) -> bool {
-    a.start() <= b.end() && b.start() <= a.end()
+#[derive(PartialEq, Debug)]
+enum WorkflowError {
+    UnknownWorkflow(WorkflowName),
+    MissingFallback(WorkflowName),
+    Cycle(Vec<WorkflowName>),
+    MissingRating(RatingName),
 }
 
-fn remove_duplicate_ranges(ranges: Vec<PartRanges>) -> Vec<PartRanges> {
-    let mut new_ranges = vec![];
-
-    for range in ranges {
-        if !new_ranges.contains(&range) {
-            new_ranges.push(range);
+impl Display for WorkflowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            WorkflowError::UnknownWorkflow(name) => write!(f, "no workflow named {name}"),
+            WorkflowError::MissingFallback(name) => {
+                write!(f, "workflow {name} doesn't end with a fallback rule")
+            }
+            WorkflowError::Cycle(names) => write!(f, "workflows loop: {}", names.join(" -> ")),
+            WorkflowError::MissingRating(name) => write!(f, "part has no {name} rating"),
         }
     }
+}
 
-    new_ranges
+struct CompiledWorkflows {
+    ratings: Vec<RatingName>,
+    nodes: Vec<Node>,
+    root: NodeId,
 }
 
-fn remove_nested_ranges(ranges: Vec<PartRanges>) -> Vec<PartRanges> {
-    if ranges.len() == 1 {
-        return ranges;
+impl CompiledWorkflows {
+    fn compile(workflows: &Workflows, ratings: &[RatingName]) -> Result<Self, WorkflowError> {
+        let mut compiled = CompiledWorkflows {
+            ratings: ratings.to_vec(),
+            nodes: vec![Node::Accept, Node::Reject],
+            root: REJECT,
+        };
+
+        // Every workflow is compiled, not just those reachable from "in", so that
+        // cycles and unreachable rules are found anywhere
+        let mut entry_nodes = HashMap::new();
+        let mut names: Vec<&WorkflowName> = workflows.keys().collect();
+        names.sort();
+        for name in names {
+            compiled.compile_workflow(workflows, name, &mut entry_nodes, &mut vec![])?;
+        }
+
+        compiled.root =
+            compiled.compile_workflow(workflows, "in", &mut entry_nodes, &mut vec![])?;
+
+        Ok(compiled)
     }
 
-    let mut new_ranges = vec![];
+    fn compile_workflow(
+        &mut self,
+        workflows: &Workflows,
+        name: &str,
+        entry_nodes: &mut HashMap<WorkflowName, NodeId>,
+        visiting: &mut Vec<WorkflowName>,
+    ) -> Result<NodeId, WorkflowError> {
+        match name {
+            "A" => return Ok(ACCEPT),
+            "R" => return Ok(REJECT),
+            _ => {}
+        }
+        if let Some(node) = entry_nodes.get(name) {
+            return Ok(*node);
+        }
+        if let Some(start) = visiting.iter().position(|visited| visited == name) {
+            let mut cycle = visiting[start..].to_vec();
+            cycle.push(name.to_string());
+            return Err(WorkflowError::Cycle(cycle));
+        }
+
+        let workflow = workflows
+            .get(name)
+            .ok_or_else(|| WorkflowError::UnknownWorkflow(name.to_string()))?;
+
+        visiting.push(name.to_string());
+
+        // Built from the last rule back, so each conditional rule knows where failing parts go
+        let mut next = None;
+        for (index, step) in workflow.iter().enumerate().rev() {
+            let rule = (name.to_string(), index);
+
+            let node = match step {
+                Step::Else(target) => Node::Jump {
+                    rule,
+                    next: self.compile_workflow(workflows, target, entry_nodes, visiting)?,
+                },
+                Step::Conditonal(operation, target) => {
+                    let (rating_name, comparison) = match operation {
+                        Operation::LessThan(rating, value) => {
+                            (rating, Comparison::LessThan(*value))
+                        }
+                        Operation::GreaterThan(rating, value) => {
+                            (rating, Comparison::GreaterThan(*value))
+                        }
+                    };
+
+                    Node::Split {
+                        rule,
+                        rating: self.rating_index(rating_name),
+                        comparison,
+                        pass: self.compile_workflow(workflows, target, entry_nodes, visiting)?,
+                        fail: next
+                            .ok_or_else(|| WorkflowError::MissingFallback(name.to_string()))?,
+                    }
+                }
+            };
 
-    for i in 0..ranges.len() {
-        let mut are_nested = false;
-        for j in 0..ranges.len() {
-            if i == j {
-                continue;
-            }
-            if is_nested(&ranges[i], &ranges[j]) {
-                are_nested = true;
-                break;
-            }
+            self.nodes.push(node);
+            next = Some(self.nodes.len() - 1);
         }
-        if !are_nested {
-            new_ranges.push(ranges[i].clone());
+
+        visiting.pop();
+
+        let entry = next.ok_or_else(|| WorkflowError::MissingFallback(name.to_string()))?;
+        entry_nodes.insert(name.to_string(), entry);
+
+        Ok(entry)
+    }
+
+    fn rating_index(&mut self, name: &str) -> RatingIndex {
+        match self.ratings.iter().position(|rating| rating == name) {
+            Some(index) => index,
+            None => {
+                self.ratings.push(name.to_string());
+                self.ratings.len() - 1
+            }
         }
     }
 
-    new_ranges
-}
+    fn uniform_bounds(&self, range: RatingValueRange) -> PartRanges {
+        vec![range; self.ratings.len()]
+    }
 
-fn is_nested(a: &PartRanges, b: &PartRanges) -> bool {
-    // Is a nested within b
-    for range_index in 0..4 {
-        if !is_nested_range(&a[range_index], &b[range_index]) {
-            return false;
-        }
+    fn accepted_regions(&self, bounds: &PartRanges) -> Vec<PartRanges> {
+        let mut accepted = vec![];
+        self.walk(
+            self.root,
+            bounds.clone(),
+            &mut accepted,
+            &mut HashSet::new(),
+        );
+
+        accepted
     }
-    true
-}
 
-fn is_nested_range(a: &RangeInclusive<RatingValue>, b: &RangeInclusive<RatingValue>) -> bool {
-    // is a nested within b
-    a.start() >= b.start() && a.end() <= b.end()
-}
+    fn accepted_volume(&self, bounds: &PartRanges) -> u128 {
+        self.accepted_regions(bounds)
+            .into_iter()
+            .map(|region| {
+                region
+                    .into_iter()
+                    .map(|range| (range.end() - range.start() + 1) as u128)
+                    .product::<u128>()
+            })
+            .sum::<u128>()
+    }
 
-fn sort_ranges(a: &PartRanges, b: &PartRanges) -> std::cmp::Ordering {
-    for range_index in 0..4 {
-        let range_ordering = compare_range(&a[range_index], &b[range_index]);
-        if range_ordering != std::cmp::Ordering::Equal {
-            return range_ordering;
-        }
+    // Rules which no part within the bounds is sent on by
+    fn unreachable_rules(&self, bounds: &PartRanges) -> Vec<Rule> {
+        let mut used = HashSet::new();
+        self.walk(self.root, bounds.clone(), &mut vec![], &mut used);
+
+        let mut unreachable: Vec<Rule> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(node, _)| !used.contains(node))
+            .filter_map(|(_, node)| match node {
+                Node::Split { rule, .. } | Node::Jump { rule, .. } => Some(rule.clone()),
+                Node::Accept | Node::Reject => None,
+            })
+            .collect();
+
+        unreachable.sort();
+        unreachable
     }
-    std::cmp::Ordering::Equal
-}
 
-fn compare_range(
-    a: &RangeInclusive<RatingValue>,
-    b: &RangeInclusive<RatingValue>,
-) -> std::cmp::Ordering {
-    match a.start().cmp(b.start()) {
-        std::cmp::Ordering::Equal => a.end().cmp(b.end()),
-        ordering => ordering,
+    fn walk(
+        &self,
+        node: NodeId,
+        region: PartRanges,
+        accepted: &mut Vec<PartRanges>,
+        used: &mut HashSet<NodeId>,
+    ) {
+        match &self.nodes[node] {
+            Node::Accept => accepted.push(region),
+            Node::Reject => {}
+            Node::Jump { next, .. } => {
+                used.insert(node);
+                self.walk(*next, region, accepted, used);
+            }
+            Node::Split {
+                rating,
+                comparison,
+                pass,
+                fail,
+                ..
+            } => {
+                let (passing, failing) = comparison.split(&region[*rating]);
+
+                if let Some(range) = passing {
+                    used.insert(node);
+
+                    let mut branch_region = region.clone();
+                    branch_region[*rating] = range;
+                    self.walk(*pass, branch_region, accepted, used);
+                }
+                if let Some(range) = failing {
+                    let mut main_region = region;
+                    main_region[*rating] = range;
+                    self.walk(*fail, main_region, accepted, used);
+                }
+            }
+        }
     }
-}
 
-fn accepted_parts(file: &str) -> u128 {
-    let (workflows, mut parts) = parse_file(file);
-
-    while let Some((part_index, part)) = parts
-        .iter()
-        .find_position(|part| part.next_workflow != "A" && part.next_workflow != "R")
-    {
-        let workflow = workflows.get(&part.next_workflow).unwrap();
-
-        for step in workflow {
-            if let Step::Conditonal(operation, next_workflow) = step {
-                if let Operation::GreaterThan(rating, value) = operation {
-                    if part.ratings[*rating] > *value {
-                        parts[part_index].next_workflow = next_workflow.to_string();
-                        break;
-                    }
-                } else if let Operation::LessThan(rating, value) = operation {
-                    if part.ratings[*rating] < *value {
-                        parts[part_index].next_workflow = next_workflow.to_string();
-                        break;
+    // The workflows a part passes through, ending in A or R
+    fn workflow_path(&self, part: &Part) -> Result<Vec<WorkflowName>, WorkflowError> {
+        let ratings = self
+            .ratings
+            .iter()
+            .map(|name| {
+                part.ratings
+                    .iter()
+                    .find(|(rating, _)| rating == name)
+                    .map(|(_, value)| *value)
+                    .ok_or_else(|| WorkflowError::MissingRating(name.clone()))
+            })
+            .collect::<Result<Vec<RatingValue>, WorkflowError>>()?;
+
+        let mut path: Vec<WorkflowName> = vec![];
+        let mut node = self.root;
+        loop {
+            let (rule, next) = match &self.nodes[node] {
+                Node::Accept => {
+                    path.push("A".to_string());
+                    return Ok(path);
+                }
+                Node::Reject => {
+                    path.push("R".to_string());
+                    return Ok(path);
+                }
+                Node::Jump { rule, next } => (rule, *next),
+                Node::Split {
+                    rule,
+                    rating,
+                    comparison,
+                    pass,
+                    fail,
+                } => {
+                    if comparison.passes(ratings[*rating]) {
+                        (rule, *pass)
+                    } else {
+                        (rule, *fail)
                     }
                 }
-            } else if let Step::Else(next_workflow) = step {
-                parts[part_index].next_workflow = next_workflow.to_string();
-                break;
-            } else {
-                panic!("Bruh");
+            };
+
+            if path.last() != Some(&rule.0) {
+                path.push(rule.0.clone());
             }
+            node = next;
         }
     }
-
-    parts
-        .into_iter()
-        .filter(|part| part.next_workflow == "A")
-        .map(|part| part.ratings.iter().sum::<usize>() as u128)
-        .sum::<u128>()
 }
 
 type WorkflowName = String;
 type Parts = Vec<Part>;
 #[derive(Debug)]
 struct Part {
-    ratings: [RatingValue; 4 as RatingIndex],
-    next_workflow: WorkflowName,
+    ratings: Vec<(RatingName, RatingValue)>,
 }
 
-type PartRanges = [RangeInclusive<RatingValue>; 4 as RatingIndex];
-struct PartRange {
-    ranges: PartRanges,
-    next_workflow: WorkflowName,
+impl Display for Part {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let ratings: Vec<String> = self
+            .ratings
+            .iter()
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect();
+
+        write!(f, "{{{}}}", ratings.join(","))
+    }
 }
 
+type PartRanges = Vec<RatingValueRange>;
+
 type Workflows = HashMap<WorkflowName, Workflow>;
 type Workflow = Vec<Step>;
 
+type RatingName = String;
 type RatingIndex = usize;
 type RatingValue = usize;
 type RatingValueRange = RangeInclusive<RatingValue>;
 #[derive(PartialEq, Debug)]
 enum Operation {
-    LessThan(RatingIndex, RatingValue),
-    GreaterThan(RatingIndex, RatingValue),
+    LessThan(RatingName, RatingValue),
+    GreaterThan(RatingName, RatingValue),
 }
 #[derive(PartialEq, Debug)]
 enum Step {
@@ -379,15 +492,13 @@ fn parse_step(step: &str) -> Step {
     let next_workflow = parts.next().unwrap().to_string();
     if operation.contains('>') {
         let mut parts = operation.split('>');
-        let rating_str = parts.next().unwrap();
-        let rating = parse_rating_str(rating_str);
+        let rating = parts.next().unwrap().to_string();
         let value = parts.next().unwrap().parse().unwrap();
 
         return Step::Conditonal(Operation::GreaterThan(rating, value), next_workflow);
     } else if operation.contains('<') {
         let mut parts = operation.split('<');
-        let rating_str = parts.next().unwrap();
-        let rating = parse_rating_str(rating_str);
+        let rating = parts.next().unwrap().to_string();
         let value = parts.next().unwrap().parse().unwrap();
 
         return Step::Conditonal(Operation::LessThan(rating, value), next_workflow);
@@ -395,40 +506,25 @@ fn parse_step(step: &str) -> Step {
     panic!("Invalid operation: {}", operation);
 }
 
-fn parse_rating_str(rating_str: &str) -> RatingIndex {
-    match rating_str {
-        "x" => 0,
-        "m" => 1,
-        "a" => 2,
-        "s" => 3,
-        _ => panic!("Invalid rating: {}", rating_str),
-    }
-}
-
 fn parse_parts(parts_str: &str) -> Parts {
     parts_str.lines().map(parse_part_line).collect()
 }
 
 fn parse_part_line(line: &str) -> Part {
-    let ratings: [RatingValue; 4] = line
+    let ratings = line
         .trim_start_matches('{')
         .trim_end_matches('}')
         .split(',')
         .map(parse_rating)
-        .collect::<Vec<RatingValue>>()
-        .try_into()
-        .unwrap();
-
-    let next_workflow = "in".to_string();
+        .collect();
 
-    Part {
-        ratings,
-        next_workflow,
-    }
+    Part { ratings }
 }
 
-fn parse_rating(rating: &str) -> RatingValue {
-    rating.split('=').nth(1).unwrap().parse().unwrap()
+fn parse_rating(rating: &str) -> (RatingName, RatingValue) {
+    let (name, value) = rating.split_once('=').unwrap();
+
+    (name.to_string(), value.parse().unwrap())
 }
 
 #[cfg(test)]
@@ -443,8 +539,14 @@ mod tests {
 
         let expected_name = "px".to_string();
         let expected_steps = vec![
-            Step::Conditonal(Operation::LessThan(2, 2006), "qkq".to_string()),
-            Step::Conditonal(Operation::GreaterThan(1, 2090), "A".to_string()),
+            Step::Conditonal(
+                Operation::LessThan("a".to_string(), 2006),
+                "qkq".to_string(),
+            ),
+            Step::Conditonal(
+                Operation::GreaterThan("m".to_string(), 2090),
+                "A".to_string(),
+            ),
             Step::Else("rfg".to_string()),
         ];
 
@@ -458,7 +560,8 @@ mod tests {
 
         let part = parse_part_line(line);
 
-        let expected_ratings = [787, 2655, 1222, 2876];
+        let expected_ratings = [("x", 787), ("m", 2655), ("a", 1222), ("s", 2876)]
+            .map(|(name, value)| (name.to_string(), value));
 
         assert_eq!(part.ratings, expected_ratings);
     }
@@ -471,100 +574,86 @@ mod tests {
     }
 
     #[test]
-    fn test_is_nested_range() {
-        let a = 2..=9;
-        let b = 1..=10;
-
-        assert!(is_nested_range(&a, &b));
-
-        let a = 1..=10;
-        let b = 2..=11;
-
-        assert!(!is_nested_range(&a, &b));
+    fn test_comparison_split() {
+        assert_eq!(
+            Comparison::LessThan(5).split(&(1..=10)),
+            (Some(1..=4), Some(5..=10))
+        );
+        assert_eq!(
+            Comparison::GreaterThan(5).split(&(1..=10)),
+            (Some(6..=10), Some(1..=5))
+        );
+        assert_eq!(
+            Comparison::LessThan(1).split(&(1..=10)),
+            (None, Some(1..=10))
+        );
+        assert_eq!(
+            Comparison::GreaterThan(10).split(&(1..=10)),
+            (None, Some(1..=10))
+        );
     }
 
     #[test]
-    fn test_remove_nested_ranges() {
-        let a = [1..=10, 1..=10, 1..=10, 1..=10];
-        let b = [2..=9, 2..=9, 2..=9, 2..=9];
-        let c = [3..=8, 3..=8, 3..=8, 3..=8];
-        let d = [4..=7, 4..=7, 4..=7, 4..=7];
-        let e = [5..=6, 5..=6, 5..=6, 5..=6];
+    fn test_workflow_path() {
+        let (workflows, parts) = parse_file("resources/2023/day19/test_input");
+        let compiled = CompiledWorkflows::compile(&workflows, &rating_names(&parts)).unwrap();
 
-        let ranges = vec![a.clone(), b, c, d, e];
+        let path = compiled.workflow_path(&parts[0]).unwrap();
 
-        let ranges = remove_nested_ranges(ranges);
-
-        let expected_ranges = vec![a];
-
-        assert_eq!(ranges, expected_ranges);
+        assert_eq!(path, ["in", "qqz", "qs", "lnx", "A"]);
     }
 
     #[test]
-    fn test_reduce_ranges_1d_overlap() {
-        let a = [1..=5, 1..=10, 1..=10, 1..=10];
-        let b = [4..=10, 1..=10, 1..=10, 1..=10];
-
-        let mut ranges = vec![a, b];
-        ranges = reduce_ranges(ranges);
-
-        let expected_range_a = [1..=3, 1..=10, 1..=10, 1..=10];
-        let expected_range_b = [4..=5, 1..=10, 1..=10, 1..=10];
-        let expected_range_c = [6..=10, 1..=10, 1..=10, 1..=10];
-
-        let expected_ranges = vec![expected_range_a, expected_range_b, expected_range_c];
+    fn test_accepted_regions_are_disjoint() {
+        let (workflows, parts) = parse_file("resources/2023/day19/test_input");
+        let compiled = CompiledWorkflows::compile(&workflows, &rating_names(&parts)).unwrap();
+
+        let regions = compiled.accepted_regions(&compiled.uniform_bounds(1..=4000));
+
+        for (i, a) in regions.iter().enumerate() {
+            for b in &regions[i + 1..] {
+                let overlapping = a
+                    .iter()
+                    .zip(b)
+                    .all(|(a, b)| a.start() <= b.end() && b.start() <= a.end());
+                assert!(!overlapping);
+            }
+        }
+    }
 
-        assert_eq!(ranges, expected_ranges);
+    #[test]
+    fn test_custom_ratings() {
+        let workflows = parse_workflows("in{hue<50:dim,sat>10:A,R}\ndim{sat<5:A,hue>60:R,A}");
+        let ratings = ["hue".to_string(), "sat".to_string()];
+        let compiled = CompiledWorkflows::compile(&workflows, &ratings).unwrap();
+
+        let bounds = vec![1..=100, 1..=20];
+        assert_eq!(compiled.accepted_volume(&bounds), 49 * 20 + 51 * 10);
+
+        // Only parts with a hue below 50 reach dim, so none can go on to reject
+        assert_eq!(
+            compiled.unreachable_rules(&bounds),
+            [("dim".to_string(), 1)]
+        );
     }
 
     #[test]
-    fn test_reduce_ranges_2d_overlap() {
-        let a = [1..=5, 1..=5, 1..=10, 1..=10];
-        let b = [3..=10, 3..=10, 1..=10, 1..=10];
-
-        let mut ranges = vec![a, b];
-        ranges = reduce_ranges(ranges);
-
-        let expected_range_a = [1..=2, 1..=5, 1..=10, 1..=10];
-        let expected_range_b = [3..=5, 1..=2, 1..=10, 1..=10];
-        let expected_range_c = [3..=5, 3..=5, 1..=10, 1..=10];
-        let expected_range_d = [3..=5, 6..=10, 1..=10, 1..=10];
-        let expected_range_e = [6..=10, 3..=10, 1..=10, 1..=10];
-
-        let expected_ranges = vec![
-            expected_range_a,
-            expected_range_b,
-            expected_range_c,
-            expected_range_d,
-            expected_range_e,
-        ];
+    fn test_workflow_cycle() {
+        let workflows = parse_workflows("in{x<5:a,R}\na{x>2:in,A}");
 
-        assert_eq!(ranges, expected_ranges);
+        let error = CompiledWorkflows::compile(&workflows, &[]).err().unwrap();
+
+        assert!(matches!(error, WorkflowError::Cycle(cycle) if cycle.len() == 3));
     }
 
     #[test]
-    fn test_reduce_ranges_2d_nest() {
-        let a = [1..=10, 1..=5, 1..=10, 1..=10];
-        let b = [4..=6, 3..=10, 1..=10, 1..=10];
-
-        let mut ranges = vec![a, b];
-        ranges = reduce_ranges(ranges);
-
-        let expected_range_a = [1..=3, 1..=5, 1..=10, 1..=10];
-        let expected_range_b = [4..=6, 1..=2, 1..=10, 1..=10];
-        let expected_range_c = [4..=6, 3..=5, 1..=10, 1..=10];
-        let expected_range_d = [4..=6, 6..=10, 1..=10, 1..=10];
-        let expected_range_e = [7..=10, 1..=5, 1..=10, 1..=10];
-
-        let expected_ranges = vec![
-            expected_range_a,
-            expected_range_b,
-            expected_range_c,
-            expected_range_d,
-            expected_range_e,
-        ];
+    fn test_unknown_workflow() {
+        let workflows = parse_workflows("in{x<5:nope,A}");
 
-        assert_eq!(ranges, expected_ranges);
+        assert_eq!(
+            CompiledWorkflows::compile(&workflows, &[]).err(),
+            Some(WorkflowError::UnknownWorkflow("nope".to_string()))
+        );
     }
 
     #[test]
@@ -573,5 +662,14 @@ mod tests {
 
         assert_eq!(accepted, 167409079868000u128);
     }
-}
 
+    #[test]
+    fn test_analyse_workflows() {
+        let mut output = vec![];
+        analyse_workflows("resources/2023/day19/test_input", &mut output);
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.contains("{x=787,m=2655,a=1222,s=2876}: in -> qqz -> qs -> lnx -> A\n"));
+        assert!(output.ends_with("Accepted volume: 167409079868000\n"));
+    }
+}
@@ -1,10 +1,10 @@
 use std::{
-    collections::{HashMap, VecDeque, HashSet},
+    collections::{BTreeSet, HashMap, HashSet, VecDeque},
+    io::Write,
     vec,
 };
 
 use itertools::Itertools;
-use num::integer::lcm;
 
 pub mod task1 {
     use super::count_pulses;
@@ -47,180 +47,379 @@ fn single_low_rx_pulse(file: &str) -> u128 {
     // common multiples, and NAND gates, along with a few graphviz diagrams.
     // Between these hints I worked back to a solution I'll describe below.
 
-    // All inputs for today's problem have the same structure, so
-    // while this isn't general for all networks, it is for all of today's inputs.
+    // In the case of my network rx is fed from gf, which is fed by 4 inverter nodes kr, zs, kf, and qk.
+    // These are fed by the output nodes bf, cx, gm, and qr of 4 subnets, each fed by one of broadcaster's outputs.
 
-    // The network is split into 4 subnets, each fed by one of broadcaster's outputs,
-    // and each having an output node. The 4 output nodes feed into an inverter node, which feed into an rx feeder.
-    // In the case of my network rx is fed from gf, the 4 inverter nodes are kr, zs, kf, and qk,
-    // and these are fed by the output nodes bf, cx, gm, and qr respectively.
-    // The 4 subnets are fed by broadcaster nodes kg, dz, ff, and bq respectively.
-    // Each subnet's input node connects to the respective output node.
+    // Run `viz 2023 20 --dot` for a graphviz diagram to illustrate what I mean (Use FDP Engine).
 
-    // Below is a link to a graphviz diagram to illustrate what I mean (Use FDP Engine).
-    // https://dreampuf.github.io/GraphvizOnline/#digraph%20G%20%7B%0A%22mh%22%20-%3E%20rz%3B%0A%22nd%22%20-%3E%20jx%3B%0A%22xt%22%20-%3E%20cx%3B%0A%22dp%22%20-%3E%20mh%3B%0A%22pz%22%20-%3E%20zg%2C%20bf%3B%0A%22rp%22%20-%3E%20jb%2C%20bf%3B%0A%22jb%22%20-%3E%20bf%2C%20kp%3B%0A%22rj%22%20-%3E%20xt%2C%20cx%3B%0A%22hg%22%20-%3E%20dl%2C%20bf%3B%0A%22pt%22%20-%3E%20gm%2C%20vv%3B%0A%22pf%22%20-%3E%20xk%2C%20qr%3B%0A%22cv%22%20-%3E%20jp%2C%20cx%3B%0A%22zg%22%20-%3E%20bb%3B%0A%22qn%22%20-%3E%20gm%2C%20bh%3B%0A%22kp%22%20-%3E%20pz%3B%0A%22kg%22%20-%3E%20gm%2C%20pt%3B%0A%22sl%22%20-%3E%20rp%3B%0A%22dz%22%20-%3E%20bf%2C%20dc%3B%0A%22hm%22%20-%3E%20cx%2C%20tz%3B%0A%22dc%22%20-%3E%20fk%3B%0A%22xk%22%20-%3E%20qr%2C%20sf%3B%0A%22kr%22%20-%3E%20gf%3B%0A%22bq%22%20-%3E%20qr%2C%20mg%3B%0A%22sf%22%20-%3E%20qr%3B%0A%22cx%22%20-%3E%20ff%2C%20vx%2C%20zs%3B%0A%22hr%22%20-%3E%20fq%2C%20gm%3B%0A%22ls%22%20-%3E%20lf%2C%20gm%3B%0A%22mf%22%20-%3E%20cx%2C%20sx%3B%0A%22vq%22%20-%3E%20gm%3B%0A%22sx%22%20-%3E%20cx%2C%20rj%3B%0A%22gm%22%20-%3E%20kg%2C%20kf%2C%20fq%2C%20nc%2C%20lf%3B%0A%22jx%22%20-%3E%20qr%2C%20zz%3B%0A%22tz%22%20-%3E%20mf%2C%20cx%3B%0A%22jp%22%20-%3E%20cx%2C%20kt%3B%0A%22bb%22%20-%3E%20hg%2C%20bf%3B%0A%22zz%22%20-%3E%20pf%2C%20qr%3B%0A%22qr%22%20-%3E%20dp%2C%20bq%2C%20nd%2C%20rz%2C%20mg%2C%20qk%2C%20mh%3B%0A%22nc%22%20-%3E%20gb%3B%0A%22kt%22%20-%3E%20hm%2C%20cx%3B%0A%22mg%22%20-%3E%20dp%3B%0A%22dl%22%20-%3E%20bf%3B%0A%22zs%22%20-%3E%20gf%3B%0A%22bf%22%20-%3E%20dz%2C%20zg%2C%20kr%2C%20sl%2C%20fk%2C%20kp%2C%20dc%3B%0A%22bh%22%20-%3E%20vq%2C%20gm%3B%0A%22kf%22%20-%3E%20gf%3B%0A%22fq%22%20-%3E%20qn%3B%0A%22vl%22%20-%3E%20vx%2C%20cx%3B%0A%22qk%22%20-%3E%20gf%3B%0A%22fk%22%20-%3E%20sl%3B%0A%22tj%22%20-%3E%20nd%2C%20qr%3B%0A%22gb%22%20-%3E%20ls%2C%20gm%3B%0A%22lf%22%20-%3E%20hr%3B%0A%22vx%22%20-%3E%20cv%3B%0A%22ff%22%20-%3E%20vl%2C%20cx%3B%0A%22broadcaster%20%22-%3E%20kg%2C%20dz%2C%20ff%2C%20bq%3B%0A%22vv%22%20-%3E%20nc%2C%20gm%3B%0A%22gf%22%20-%3E%20rx%3B%0A%22rz%22%20-%3E%20tj%3B%0A%7D
+    // The idea behind this solution is that the conjunctions feeding rx form a tree of NAND gates
+    // over independent subnets. Ie. rx only gets a low pulse when every subnet output pulses the
+    // right way in the same press.
 
-    // The idea behind this solution is that the 4 inverter nodes together with the rx feeder form a NAND gate
-    // between the 4 subnets. Ie. the rx feeder will only pulse when all 4 subnet outputs are low.
+    // Working back from rx, a conjunction sending low needs all of its feeders to send high, and an
+    // inverter sending high needs its feeder to send low. The tree is followed for as long as the
+    // feeders' upstream modules don't overlap, and what's left are the subnet outputs.
 
-    // Since the network starts with all nodes low, we can consider each subnet independently. By finding their
-    // cycle periods, we can use the lowest common multiple of these to get the period
-    // of the NAND gate.
-
-    // This solution starts by marking the rx_feeder, the nand gate nodes, and the nand gate inputs.
-    // Then, by working from each broadcast node, the subnets are divided, and the nand gate inputs are assigned.
-    // Each subnet then has it's period calculated, and the lowest common multiple is returned.
+    // Since the network starts with all nodes low, we can consider each subnet independently. By finding
+    // the first press each output pulses, and how often it repeats, the chinese remainder theorem gives
+    // the first press they all line up.
     let modules = parse_file(file);
 
-    let (_rx_feeder, nand_gate_modules, _nand_gate_inputs, subnet_inputs) =
-        find_critical_modules(&modules);
+    first_low_rx_press(&modules).unwrap_or_else(|error| panic!("{error}"))
+}
 
-    let periods: Vec<u128> = nand_gate_modules
-        .into_iter()
-        .zip(subnet_inputs)
-        .map(|(nand_module, subnet_input)| {
-            find_subnet(&modules, subnet_input, nand_module)
-        })
-        .map(|subnet| {
-            find_output_period(subnet)
-        }).collect();
+// Subnets with longer periods than this are treated as never repeating
+const MAX_PRESSES: u128 = 1 << 16;
+
+#[derive(PartialEq, Debug)]
+enum NetworkError {
+    MissingRx,
+    MultipleRxFeeders,
+    // The subnets feeding the module share modules, so they can't be solved separately
+    NotDecomposable(ModuleName),
+    NoCycle(ModuleName),
+    NeverSent(ModuleName),
+    IrregularOutput(ModuleName),
+    NeverAligns,
+}
 
-    periods.into_iter().fold(1, lcm)
+impl std::fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            NetworkError::MissingRx => write!(f, "nothing feeds rx"),
+            NetworkError::MultipleRxFeeders => write!(f, "rx has more than one feeder"),
+            NetworkError::NotDecomposable(module) => {
+                write!(f, "the subnets feeding {module} share modules")
+            }
+            NetworkError::NoCycle(module) => {
+                write!(
+                    f,
+                    "the subnet feeding {module} doesn't repeat within {MAX_PRESSES} presses"
+                )
+            }
+            NetworkError::NeverSent(module) => write!(f, "{module} never sends the pulse rx needs"),
+            NetworkError::IrregularOutput(module) => {
+                write!(f, "{module} doesn't pulse at a fixed period")
+            }
+            NetworkError::NeverAligns => write!(f, "the subnet outputs never line up"),
+        }
+    }
 }
 
-fn find_critical_modules(modules: &ModuleMap) -> (String, Vec<String>, Vec<String>, Vec<String>) {
-    let rx_feeder = modules
-        .iter()
-        .find(|(_, module)| get_connections(module).contains(&"rx".to_string()))
-        .unwrap()
-        .0
-        .clone();
+// A module sending a pulse of the given state at some point during a press
+type Target = (ModuleName, State);
 
-    let nand_gate_modules = modules
-        .iter()
-        .filter(|(_, module)| get_connections(module).contains(&rx_feeder))
-        .map(|(name, _)| name.clone())
-        .collect::<Vec<String>>();
+fn first_low_rx_press(modules: &ModuleMap) -> Result<u128, NetworkError> {
+    let rx_feeder = match &find_feeders(modules, "rx")[..] {
+        [] => return Err(NetworkError::MissingRx),
+        [feeder] => feeder.clone(),
+        _ => return Err(NetworkError::MultipleRxFeeders),
+    };
+
+    // A conjunction that can't be split would leave the whole network to simulate at once
+    let target = (rx_feeder.clone(), State::Low);
+    let targets = find_independent_targets(modules, target.clone());
+    if targets == [target] && matches!(modules.get(&rx_feeder), Some(Module::Conjunction(..))) {
+        return Err(NetworkError::NotDecomposable(rx_feeder));
+    }
 
-    let nand_gate_inputs = nand_gate_modules
+    let schedules = targets
         .iter()
-        .map(|nand_module| {
-            modules
-                .iter()
-                .find(|(_, module)| get_connections(module).contains(nand_module))
-                .unwrap()
-                .0
-                .clone()
+        .map(|target| find_output_schedule(modules, target))
+        .collect::<Result<Vec<(u128, u128)>, NetworkError>>()?;
+
+    // Every output has to have pulsed at least once, so the answer can't be before the latest first pulse
+    let latest_first = schedules.iter().map(|(first, _)| *first).max().unwrap();
+
+    let (remainder, modulus) = schedules
+        .into_iter()
+        .try_fold((0, 1), |(a1, m1), (first, period)| {
+            crt(a1, m1, (first % period) as i128, period as i128)
         })
-        .collect::<Vec<String>>();
+        .ok_or(NetworkError::NeverAligns)?;
+
+    let remainder = remainder as u128;
+    let modulus = modulus as u128;
+    if remainder >= latest_first {
+        return Ok(remainder);
+    }
+    Ok(remainder + (latest_first - remainder).div_ceil(modulus) * modulus)
+}
+
+// Solves x = a1 mod m1 and x = a2 mod m2, giving x mod lcm(m1, m2)
+fn crt(a1: i128, m1: i128, a2: i128, m2: i128) -> Option<(i128, i128)> {
+    let (gcd, inverse, _) = extended_gcd(m1, m2);
+    if (a2 - a1) % gcd != 0 {
+        return None;
+    }
 
-    let subnet_inputs = nand_gate_inputs
+    let lcm = m1 / gcd * m2;
+    let step = ((a2 - a1) / gcd * inverse).rem_euclid(m2 / gcd);
+
+    Some(((a1 + m1 * step).rem_euclid(lcm), lcm))
+}
+
+// Returns (gcd, x, y) with a * x + b * y = gcd
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        return (a, 1, 0);
+    }
+
+    let (gcd, x, y) = extended_gcd(b, a % b);
+    (gcd, y, x - a / b * y)
+}
+
+fn find_independent_targets(modules: &ModuleMap, target: Target) -> Vec<Target> {
+    let (module_name, state) = &target;
+    let feeders = find_feeders(modules, module_name);
+
+    let children: Vec<Target> = match (modules.get(module_name), state) {
+        (Some(Module::Conjunction(..)), State::Low) => feeders
+            .into_iter()
+            .map(|feeder| (feeder, State::High))
+            .collect(),
+        (Some(Module::Conjunction(..)), State::High) if feeders.len() == 1 => {
+            vec![(feeders[0].clone(), State::Low)]
+        }
+        _ => return vec![target],
+    };
+
+    // The children can only be solved separately if nothing upstream of them is shared,
+    // other than the broadcaster, and they don't feed back into this module
+    let cones: Vec<HashSet<ModuleName>> = children
         .iter()
-        .map(|subnet_output| {
-            modules
-                .iter()
-                .filter(|(_, module)| get_connections(module).contains(subnet_output))
-                .filter(|(module_name, _)| {
-                    get_connections(modules.get(&"broadcaster".to_string()).unwrap())
-                        .contains(module_name)
-                })
-                .map(|(name, _)| name.clone())
-                .next()
-                .unwrap()
+        .map(|(child, _)| find_upstream_modules(modules, child))
+        .collect();
+
+    let feeds_back = cones.iter().any(|cone| cone.contains(module_name));
+    let overlapping = cones.iter().enumerate().any(|(i, cone)| {
+        cones[i + 1..].iter().any(|other| {
+            cone.intersection(other)
+                .any(|module| module != "broadcaster")
         })
-        .collect::<Vec<String>>();
+    });
+
+    if feeds_back || overlapping {
+        return vec![target];
+    }
 
-    (
-        rx_feeder,
-        nand_gate_modules,
-        nand_gate_inputs,
-        subnet_inputs,
-    )
+    children
+        .into_iter()
+        .flat_map(|child| find_independent_targets(modules, child))
+        .collect()
 }
 
-fn find_subnet(modules: &ModuleMap, subnet_input: String, subnet_output: String) -> ModuleMap{
-    let mut subnet_set = HashSet::new();
-    subnet_set.insert(subnet_input.clone());
+fn find_feeders(modules: &ModuleMap, module_name: &str) -> Vec<ModuleName> {
+    modules
+        .iter()
+        .filter(|(_, module)| get_connections(module).iter().any(|c| c == module_name))
+        .map(|(name, _)| name.clone())
+        .sorted()
+        .collect()
+}
 
-    loop{
-        let new_connections = find_new_connections(modules, &subnet_set, subnet_output.clone());
+fn find_upstream_modules(modules: &ModuleMap, module_name: &str) -> HashSet<ModuleName> {
+    let mut upstream = HashSet::new();
+    upstream.insert(module_name.to_string());
 
-        if new_connections.is_empty() {
-            break;
+    let mut frontier = vec![module_name.to_string()];
+    while let Some(current) = frontier.pop() {
+        for feeder in find_feeders(modules, &current) {
+            if upstream.insert(feeder.clone()) {
+                frontier.push(feeder);
+            }
         }
-
-        subnet_set = subnet_set.union(&new_connections).cloned().collect();
     }
 
-    subnet_set.insert(subnet_output.clone());
+    upstream
+}
+
+fn find_subnet(modules: &ModuleMap, subnet_output: &str) -> ModuleMap {
+    let subnet_set = find_upstream_modules(modules, subnet_output);
 
     let mut subnet: ModuleMap = modules
         .iter()
         .filter(|(name, _)| subnet_set.contains(*name))
         .map(|(name, module)| (name.clone(), module.clone()))
         .collect();
-    subnet.insert("broadcaster".to_string(), Module::Broadcast(vec![subnet_input.clone()]));
+
+    // Only broadcast into this subnet, so the pulse counts stay local to it
+    if let Some(Module::Broadcast(connections)) = subnet.get_mut("broadcaster") {
+        connections.retain(|connection| subnet_set.contains(connection));
+    }
 
     subnet
 }
 
-fn find_new_connections(modules: &ModuleMap, subnet_set: &HashSet<String>, subnet_output: String) -> HashSet<String> {
-    let mut new_connections = HashSet::new();
-
-    subnet_set.iter().for_each(|subnet_module| {
-        let module = modules.get(subnet_module).unwrap();
-        let connections = get_connections(module);
+// Returns the first press the target pulses on, and how many presses until it repeats
+fn find_output_schedule(
+    modules: &ModuleMap,
+    target: &Target,
+) -> Result<(u128, u128), NetworkError> {
+    let (module_name, state) = target;
+    let mut subnet = find_subnet(modules, module_name);
 
-        connections.iter().for_each(|connection| {
-            if !subnet_set.contains(connection) && connection != &subnet_output {
-                new_connections.insert(connection.clone());
+    let mut state_memory: HashMap<String, u128> = HashMap::new();
+    let mut pulse_presses = vec![];
+
+    for presses in 0..MAX_PRESSES {
+        if let Some(cycle_start) = state_memory.insert(hash_modules(&subnet), presses) {
+            let period = presses - cycle_start;
+            let first = *pulse_presses
+                .first()
+                .ok_or_else(|| NetworkError::NeverSent(module_name.clone()))?;
+
+            // Any pulses before the cycle starts have to fit the same pattern as the ones in it
+            let expected: Vec<u128> = (first..=presses).step_by(period as usize).collect();
+            if pulse_presses != expected {
+                return Err(NetworkError::IrregularOutput(module_name.clone()));
             }
+
+            return Ok((first, period));
+        }
+
+        let mut pulsed = false;
+        subnet = push_button_watching(subnet, |(_, from, pulse)| {
+            pulsed |= from == module_name && pulse == state;
         });
-    });
 
-    new_connections
+        if pulsed {
+            pulse_presses.push(presses + 1);
+        }
+    }
+
+    Err(NetworkError::NoCycle(module_name.clone()))
 }
 
-fn find_output_period(mut modules: ModuleMap) -> u128 {
-    let mut state_memory: HashMap<String, u128> = HashMap::new();
+pub fn write_dot(file: &str, presses: Option<usize>, mut output: impl Write) {
+    let modules = parse_file(file);
+
+    let edge_states = presses.map(|presses| last_edge_states(modules.clone(), presses));
+
+    write!(output, "{}", network_dot(&modules, edge_states.as_ref())).unwrap();
+}
+
+// (From, To) -> State of the last pulse sent along the connection
+type EdgeStates = HashMap<(ModuleName, ModuleName), State>;
 
-    let mut period = 0;
+fn last_edge_states(mut modules: ModuleMap, presses: usize) -> EdgeStates {
+    let mut edge_states = EdgeStates::new();
 
-    loop{
-        let subnet_hash = hash_modules(&modules);
+    for _ in 0..presses {
+        modules = push_button_watching(modules, |(module_to_name, module_from_name, state)| {
+            edge_states.insert(
+                (module_from_name.clone(), module_to_name.clone()),
+                state.clone(),
+            );
+        });
+    }
+
+    edge_states
+}
+
+fn network_dot(modules: &ModuleMap, edge_states: Option<&EdgeStates>) -> String {
+    let mut lines = vec!["digraph G {".to_string()];
+
+    // Modules without a definition, like rx, only receive pulses
+    let sinks: BTreeSet<&ModuleName> = modules
+        .values()
+        .flat_map(get_connections)
+        .filter(|connection| !modules.contains_key(*connection))
+        .collect();
+
+    let node = |name: &ModuleName| match modules.get(name) {
+        Some(Module::Broadcast(_)) => format!("\"{name}\" [shape=doubleoctagon];"),
+        Some(Module::FlipFlop(..)) => format!("\"{name}\" [shape=box, label=\"%{name}\"];"),
+        Some(Module::Conjunction(..)) => {
+            format!("\"{name}\" [shape=diamond, label=\"&{name}\"];")
+        }
+        None => format!("\"{name}\" [shape=doublecircle];"),
+    };
+
+    // Each subnet feeding rx gets its own cluster, when the network splits into them
+    let subnets: Vec<(ModuleName, ModuleMap)> = match &find_feeders(modules, "rx")[..] {
+        [rx_feeder] => find_independent_targets(modules, (rx_feeder.clone(), State::Low))
+            .into_iter()
+            .map(|(subnet_output, _)| {
+                let subnet = find_subnet(modules, &subnet_output);
+                (subnet_output, subnet)
+            })
+            .collect(),
+        _ => vec![],
+    };
+
+    let mut clustered: HashSet<&ModuleName> = HashSet::new();
+    for (index, (subnet_output, subnet)) in subnets.iter().enumerate() {
+        lines.push(format!("    subgraph cluster_{index} {{"));
+        lines.push(format!("        label=\"{subnet_output}\";"));
 
-        if state_memory.contains_key(&subnet_hash) {
-            return period - state_memory.get(&subnet_hash).unwrap();
+        for name in subnet.keys().sorted() {
+            if name != "broadcaster" && clustered.insert(name) {
+                lines.push(format!("        {}", node(name)));
+            }
         }
 
-        state_memory.insert(subnet_hash, period);
+        lines.push("    }".to_string());
+    }
 
-        (_, modules, _) = push_button(modules);
+    for name in modules.keys().chain(sinks).sorted() {
+        if !clustered.contains(name) {
+            lines.push(format!("    {}", node(name)));
+        }
+    }
 
-        period += 1;
+    for (name, module) in modules.iter().sorted_by_key(|(name, _)| *name) {
+        for connection in get_connections(module) {
+            let edge = format!("\"{name}\" -> \"{connection}\"");
+
+            let colour =
+                match edge_states.map(|states| states.get(&(name.clone(), connection.clone()))) {
+                    None => None,
+                    Some(Some(State::High)) => Some("color=red"),
+                    Some(Some(State::Low)) => Some("color=blue"),
+                    Some(None) => Some("color=gray, style=dashed"),
+                };
+
+            match colour {
+                Some(colour) => lines.push(format!("    {edge} [{colour}];")),
+                None => lines.push(format!("    {edge};")),
+            }
+        }
     }
+
+    lines.push("}".to_string());
+    lines.join("\n") + "\n"
 }
 
 fn hash_modules(modules: &ModuleMap) -> String {
     // Hashes a subnet into a string
-    let mut mod_states = modules.iter().map(|(name, module)| {
-        let module_state = match module {
-            Module::Broadcast(_) => "0".to_string(),
-            Module::FlipFlop(_, state) => match state {
-                State::High => "1".to_string(),
-                State::Low => "2".to_string(),
-            },
-            Module::Conjunction(_, feeders) => {
-                let feeder_states = feeders.iter().map(|(_, state)| match state {
-                    State::High => "1",
-                    State::Low => "2",
-                }).collect::<Vec<&str>>();
-
-                feeder_states.into_iter().fold("".to_string(), |acc, state| format!("{}{}", acc, state)).to_string()
-            }
-        };
-        format!("{}{}", name, module_state)
-    }).collect::<Vec<String>>();
+    let mut mod_states = modules
+        .iter()
+        .map(|(name, module)| {
+            let module_state = match module {
+                Module::Broadcast(_) => "0".to_string(),
+                Module::FlipFlop(_, state) => match state {
+                    State::High => "1".to_string(),
+                    State::Low => "2".to_string(),
+                },
+                Module::Conjunction(_, feeders) => {
+                    let feeder_states = feeders
+                        .iter()
+                        .map(|(_, state)| match state {
+                            State::High => "1",
+                            State::Low => "2",
+                        })
+                        .collect::<Vec<&str>>();
+
+                    feeder_states
+                        .into_iter()
+                        .fold("".to_string(), |acc, state| format!("{}{}", acc, state))
+                        .to_string()
+                }
+            };
+            format!("{}{}", name, module_state)
+        })
+        .collect::<Vec<String>>();
     mod_states.sort();
     mod_states.join("").to_string()
 }
@@ -228,25 +427,36 @@ fn hash_modules(modules: &ModuleMap) -> String {
 // (To, From, State)
 type Pulse = (ModuleName, ModuleName, State);
 type RxPresses = usize;
-fn push_button(mut modules: ModuleMap) -> ((u128, u128), ModuleMap, RxPresses) {
-    let mut pulse_queue: VecDeque<Pulse> = VecDeque::new();
-
-    pulse_queue.push_back(("broadcaster".to_string(), "button".to_string(), State::Low));
-
+fn push_button(modules: ModuleMap) -> ((u128, u128), ModuleMap, RxPresses) {
     let mut high_pulses = 0;
     let mut low_pulses = 0;
 
     let mut low_rx_pulses = 0;
 
-    while let Some((module_to_name, module_from_name, pulse_state)) = pulse_queue.pop_front() {
+    let modules = push_button_watching(modules, |(module_to_name, _, pulse_state)| {
         match pulse_state {
             State::High => high_pulses += 1,
             State::Low => low_pulses += 1,
         }
 
-        if module_to_name == "rx" && pulse_state == State::Low {
+        if module_to_name == "rx" && pulse_state == &State::Low {
             low_rx_pulses += 1;
         }
+    });
+
+    ((high_pulses, low_pulses), modules, low_rx_pulses)
+}
+
+// Pushes the button, calling on_pulse with every pulse sent
+fn push_button_watching(mut modules: ModuleMap, mut on_pulse: impl FnMut(&Pulse)) -> ModuleMap {
+    let mut pulse_queue: VecDeque<Pulse> = VecDeque::new();
+
+    pulse_queue.push_back(("broadcaster".to_string(), "button".to_string(), State::Low));
+
+    while let Some(pulse) = pulse_queue.pop_front() {
+        on_pulse(&pulse);
+
+        let (module_to_name, module_from_name, pulse_state) = pulse;
 
         if !modules.contains_key(&module_to_name) {
             continue;
@@ -292,7 +502,7 @@ fn push_button(mut modules: ModuleMap) -> ((u128, u128), ModuleMap, RxPresses) {
         }
     }
 
-    ((high_pulses, low_pulses), modules, low_rx_pulses)
+    modules
 }
 
 fn pulse_flip_flop(module_name: ModuleName, module: Module, pulse: State) -> (Module, Vec<Pulse>) {
@@ -509,44 +719,112 @@ mod tests {
     }
 
     #[test]
-    fn test_find_critical_modules() {
+    fn test_find_independent_targets() {
         let modules = parse_file("resources/2023/day20/input");
 
-        let (rx_feeder, nand_gate_modules, nand_gate_inputs, subnet_inputs) =
-            find_critical_modules(&modules);
-
-        assert_eq!(rx_feeder, "gf");
-
-        let expected_nand_gate_modules = ["kr", "zs", "kf", "qk"];
-        assert_eq!(nand_gate_modules.len(), expected_nand_gate_modules.len());
-        assert!(expected_nand_gate_modules
-            .iter()
-            .all(|module| nand_gate_modules.contains(&module.to_string())));
-
-        let expected_nand_gate_inputs = ["bf", "cx", "gm", "qr"];
-        assert_eq!(nand_gate_inputs.len(), expected_nand_gate_inputs.len());
-        assert!(expected_nand_gate_inputs
-            .iter()
-            .all(|input| nand_gate_inputs.contains(&input.to_string())));
+        let targets = find_independent_targets(&modules, ("gf".to_string(), State::Low));
 
-        let expected_subnet_inputs = ["kg", "dz", "ff", "bq"];
-        assert_eq!(subnet_inputs.len(), expected_subnet_inputs.len());
-        assert!(expected_subnet_inputs
+        let expected_targets = ["bf", "cx", "gm", "qr"];
+        assert_eq!(targets.len(), expected_targets.len());
+        assert!(expected_targets
             .iter()
-            .all(|input| subnet_inputs.contains(&input.to_string())));
+            .all(|module| targets.contains(&(module.to_string(), State::Low))));
     }
 
     #[test]
-    fn test_find_subnet(){
+    fn test_find_subnet() {
         let modules = parse_file("resources/2023/day20/input");
 
-        let subnet = find_subnet(&modules, "dz".to_string(), "kr".to_string());
-
-        let expected_modules = ["dz", "kr", "broadcaster", "dc", "fk", "bf", "sl", "dl", "hg", "rp", "jb", "bb", "zg", "kp", "pz"];
+        let subnet = find_subnet(&modules, "kr");
+
+        let expected_modules = [
+            "dz",
+            "kr",
+            "broadcaster",
+            "dc",
+            "fk",
+            "bf",
+            "sl",
+            "dl",
+            "hg",
+            "rp",
+            "jb",
+            "bb",
+            "zg",
+            "kp",
+            "pz",
+        ];
         assert_eq!(subnet.len(), expected_modules.len());
         assert!(expected_modules
             .iter()
             .all(|module| subnet.contains_key(&module.to_string())));
     }
-}
 
+    #[test]
+    fn test_single_low_rx_pulse_with_offsets() {
+        // Two counters, pulsing on presses 3, 7, 11, ... and 7, 15, 23, ...,
+        // which the lowest common multiple of their periods would miss
+        let file = "resources/2023/day20/test_input3";
+        assert_eq!(single_low_rx_pulse(file), 7);
+
+        let mut modules = parse_file(file);
+        let mut presses = 0;
+        loop {
+            let (_, next_modules, low_rx_pulses) = push_button(modules);
+            modules = next_modules;
+            presses += 1;
+
+            if low_rx_pulses > 0 {
+                break;
+            }
+        }
+        assert_eq!(presses, 7);
+    }
+
+    #[test]
+    fn test_missing_rx() {
+        let modules = parse_file("resources/2023/day20/test_input");
+
+        assert_eq!(first_low_rx_press(&modules), Err(NetworkError::MissingRx));
+    }
+
+    #[test]
+    fn test_undecomposable_network() {
+        // Both subnets feeding out are driven by a1
+        let modules = parse_file("resources/2023/day20/test_input4");
+
+        let error = first_low_rx_press(&modules).unwrap_err();
+        assert_eq!(error, NetworkError::NotDecomposable("out".to_string()));
+        assert_eq!(error.to_string(), "the subnets feeding out share modules");
+    }
+
+    #[test]
+    fn test_network_dot() {
+        let modules = parse_file("resources/2023/day20/test_input");
+
+        let dot = network_dot(&modules, None);
+
+        assert!(dot.starts_with("digraph G {\n"));
+        assert!(dot.contains("    \"broadcaster\" [shape=doubleoctagon];\n"));
+        assert!(dot.contains("    \"a\" [shape=box, label=\"%a\"];\n"));
+        assert!(dot.contains("    \"inv\" [shape=diamond, label=\"&inv\"];\n"));
+        assert!(dot.contains("    \"broadcaster\" -> \"a\";\n"));
+        assert!(!dot.contains("subgraph"));
+    }
+
+    #[test]
+    fn test_network_dot_clusters_and_states() {
+        let mut output = vec![];
+        write_dot("resources/2023/day20/test_input3", Some(3), &mut output);
+        let dot = String::from_utf8(output).unwrap();
+
+        assert_eq!(dot.matches("subgraph cluster_").count(), 2);
+        assert!(dot.contains("        \"ca\" [shape=diamond, label=\"&ca\"];\n"));
+        assert!(dot.contains("    \"rx\" [shape=doublecircle];\n"));
+
+        // On the third press the two bit counter is full, and the three bit one isn't
+        assert!(dot.contains("    \"ca\" -> \"ia\" [color=blue];\n"));
+        assert!(dot.contains("    \"cb\" -> \"ib\" [color=red];\n"));
+        assert!(dot.contains("    \"out\" -> \"rx\" [color=red];\n"));
+    }
+}
@@ -1,6 +1,8 @@
 use std::{
-    collections::{HashMap, HashSet, VecDeque},
+    collections::{HashMap, HashSet},
+    fmt::Display,
     fs,
+    io::Write,
 };
 
 pub mod task1 {
@@ -23,8 +25,42 @@ type Rules = HashMap<u128, HashSet<u128>>;
 type Update = Vec<u128>;
 type Updates = Vec<Update>;
 
+// A page that has to come before another
+type Rule = (u128, u128);
+
+#[derive(Debug, PartialEq)]
+struct Ordering {
+    pages: Update,
+    // Whether the rules leave only one way to order the pages
+    unique: bool,
+}
+
+#[derive(Debug, PartialEq)]
+enum OrderError {
+    // Pages that each have to come before the next, with the last before the first
+    Cycle(Vec<u128>),
+}
+
+impl Display for OrderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            OrderError::Cycle(pages) => {
+                let cycle: Vec<String> = pages
+                    .iter()
+                    .chain(pages.first())
+                    .map(|page| page.to_string())
+                    .collect();
+                write!(f, "rules form a cycle {}", cycle.join(" -> "))
+            }
+        }
+    }
+}
+
 fn read_file(file: &str) -> (Updates, Rules) {
-    let contents = fs::read_to_string(file).unwrap();
+    parse(&fs::read_to_string(file).unwrap())
+}
+
+fn parse(contents: &str) -> (Updates, Rules) {
     let mut contents = contents.split("\n\n");
 
     let rules: Vec<&str> = contents.next().unwrap().lines().collect();
@@ -32,7 +68,7 @@ fn read_file(file: &str) -> (Updates, Rules) {
 
     // A rule states that page A must be printed before page B
     // Ie. rules.get(B) = A
-    let rules: Vec<(u128, u128)> = rules
+    let rules: Vec<Rule> = rules
         .into_iter()
         .map(|line| {
             let mut line = line.split("|");
@@ -45,7 +81,7 @@ fn read_file(file: &str) -> (Updates, Rules) {
 
     let mut rule_map: Rules = HashMap::new();
     for (x, y) in rules {
-        rule_map.entry(y).or_insert(HashSet::new()).insert(x);
+        rule_map.entry(y).or_default().insert(x);
     }
 
     let pages: Updates = pages
@@ -81,7 +117,10 @@ fn sum_middle_corrected_page_numbers(file: &str) -> u128 {
     pages
         .into_iter()
         .filter(|update| !is_valid_update(&rules, update))
-        .map(|update| correct_invalid_update(&rules, &update))
+        .map(|update| match order_update(&rules, &update) {
+            Ok(ordering) => ordering.pages,
+            Err(error) => panic!("Can't order {update:?}, {error}"),
+        })
         .map(|page| {
             let len = page.len();
             let middle_index = len / 2;
@@ -91,71 +130,138 @@ fn sum_middle_corrected_page_numbers(file: &str) -> u128 {
         .sum()
 }
 
-fn find_relevant_rules(rules: Rules, update: &Update) -> Rules {
-    let pages_to_print = update.into_iter().map(|x| *x).collect::<HashSet<u128>>();
+fn find_relevant_rules(rules: &Rules, update: &Update) -> Rules {
+    let pages_to_print = update.iter().copied().collect::<HashSet<u128>>();
 
-    let relevant_rules: Rules = rules
-        .into_iter()
-        .filter(|(y, _)| pages_to_print.contains(y))
-        .map(|(y, x_set)| {
-            let relevant_x = x_set
+    update
+        .iter()
+        .map(|page| {
+            let relevant_x = rules
+                .get(page)
                 .into_iter()
+                .flatten()
                 .filter(|x| pages_to_print.contains(x))
-                .map(|x| x)
+                .copied()
                 .collect::<HashSet<u128>>();
-            (y, relevant_x)
+            (*page, relevant_x)
         })
-        .collect();
+        .collect()
+}
 
-    relevant_rules
+// Rules broken by the update, grouped by the page that was printed too early
+fn violated_rules(rules: &Rules, update: &Update) -> Vec<Rule> {
+    let relevant_rules = find_relevant_rules(rules, update);
+
+    update
+        .iter()
+        .enumerate()
+        .flat_map(|(i, page)| {
+            let prior = &relevant_rules[page];
+            update[i + 1..]
+                .iter()
+                .filter(|later| prior.contains(later))
+                .map(|later| (*later, *page))
+        })
+        .collect()
 }
 
 fn is_valid_update(rules: &Rules, update: &Update) -> bool {
-    let relevant_rules = find_relevant_rules(rules.clone(), update);
-
-    let mut printed_pages: HashSet<u128> = HashSet::new();
+    violated_rules(rules, update).is_empty()
+}
 
-    for page in update {
-        if let Some(prior) = relevant_rules.get(page) {
-            if !prior.is_subset(&printed_pages) {
-                return false;
-            }
+// Kahn's algorithm, taking pages in their order in the update whenever the rules allow
+// more than one. The order is unique when there is never a choice to make
+fn order_update(rules: &Rules, update: &Update) -> Result<Ordering, OrderError> {
+    let mut remaining = find_relevant_rules(rules, update);
+
+    let mut ordering = Ordering {
+        pages: vec![],
+        unique: true,
+    };
+
+    while !remaining.is_empty() {
+        let ready: Vec<u128> = update
+            .iter()
+            .filter(|page| remaining.get(page).is_some_and(|prior| prior.is_empty()))
+            .copied()
+            .collect();
+
+        let Some(&page) = ready.first() else {
+            return Err(OrderError::Cycle(find_cycle(&remaining)));
+        };
+
+        ordering.unique &= ready.len() == 1;
+        ordering.pages.push(page);
+        remaining.remove(&page);
+        for prior in remaining.values_mut() {
+            prior.remove(&page);
         }
-
-        printed_pages.insert(*page);
     }
 
-    true
+    Ok(ordering)
 }
 
-fn correct_invalid_update(rules: &Rules, update: &Update) -> Update {
-    let relevant_rules = find_relevant_rules(rules.clone(), update);
+// Every page left over has a prior page that's also left over, so following them back
+// must eventually repeat a page
+fn find_cycle(remaining: &Rules) -> Vec<u128> {
+    let mut page = *remaining.keys().min().unwrap();
+    let mut path = vec![];
 
-    let mut corrected_update: Update = Vec::new();
+    while !path.contains(&page) {
+        path.push(page);
+        page = *remaining[&page].iter().min().unwrap();
+    }
 
-    let mut update_queue: VecDeque<u128> = update.clone().into();
+    let start = path.iter().position(|&p| p == page).unwrap();
+    let mut cycle = path.split_off(start);
 
-    while let Some(page) = update_queue.pop_front() {
-        // If page has no rules, add to printed pages and continue
-        if let None = relevant_rules.get(&page) {
-            corrected_update.push(page);
-            continue;
-        }
-
-        let prior = relevant_rules.get(&page).unwrap();
+    // The path runs from later pages back to prior ones
+    cycle.reverse();
+    let first = (0..cycle.len()).min_by_key(|&i| cycle[i]).unwrap();
+    cycle.rotate_left(first);
+    cycle
+}
 
-        // If all prior pages have been printed, add to printed pages and continue
-        let printed_pages: HashSet<u128> = corrected_update.iter().map(|x| *x).collect();
-        if prior.is_subset(&printed_pages) {
-            corrected_update.push(page);
-            continue;
-        }
+// Lists the rules each update breaks, and how it should be ordered
+pub fn check_updates(file: &str, mut output: impl Write) {
+    let (updates, rules) = read_file(file);
+
+    let join = |pages: &[u128]| {
+        pages
+            .iter()
+            .map(|page| page.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+
+    for update in &updates {
+        let violated: Vec<String> = violated_rules(&rules, update)
+            .iter()
+            .map(|(before, after)| format!("{before}|{after}"))
+            .collect();
+
+        let summary = match order_update(&rules, update) {
+            Err(error) => format!("can't be ordered, {error}"),
+            Ok(ordering) => {
+                let alternatives = if ordering.unique {
+                    ""
+                } else {
+                    " (not the only order)"
+                };
+                if violated.is_empty() {
+                    format!("is in order{alternatives}")
+                } else {
+                    format!(
+                        "breaks {}, and should be {}{alternatives}",
+                        violated.join(" "),
+                        join(&ordering.pages)
+                    )
+                }
+            }
+        };
 
-        // If page is not in a valid location, requeue
-        update_queue.push_back(page);
+        writeln!(output, "{} {summary}", join(update)).unwrap();
     }
-
-    corrected_update
 }
 
 #[cfg(test)]
@@ -177,4 +283,54 @@ mod tests {
             123
         );
     }
+
+    #[test]
+    fn test_violated_rules() {
+        let (updates, rules) = read_file("resources/2024/day05/test.txt");
+
+        assert_eq!(violated_rules(&rules, &updates[0]), []);
+        assert_eq!(violated_rules(&rules, &updates[4]), [(29, 13)]);
+        assert_eq!(
+            violated_rules(&rules, &updates[5]),
+            [(75, 13), (29, 13), (47, 13), (47, 29)]
+        );
+    }
+
+    #[test]
+    fn test_order_update() {
+        let (updates, rules) = read_file("resources/2024/day05/test.txt");
+        assert_eq!(
+            order_update(&rules, &updates[5]),
+            Ok(Ordering {
+                pages: vec![97, 75, 47, 29, 13],
+                unique: true
+            })
+        );
+
+        // Nothing decides whether 2 or 3 goes first
+        let (updates, rules) = parse("1|2\n1|3\n\n3,2,1");
+        assert_eq!(
+            order_update(&rules, &updates[0]),
+            Ok(Ordering {
+                pages: vec![1, 3, 2],
+                unique: false
+            })
+        );
+
+        let (updates, rules) = parse("1|2\n2|3\n3|4\n4|2\n\n4,3,2,1");
+        let error = order_update(&rules, &updates[0]).unwrap_err();
+        assert_eq!(error, OrderError::Cycle(vec![2, 3, 4]));
+        assert_eq!(error.to_string(), "rules form a cycle 2 -> 3 -> 4 -> 2");
+    }
+
+    #[test]
+    fn test_check_updates() {
+        let mut output = vec![];
+        check_updates("resources/2024/day05/test.txt", &mut output);
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines[0], "75,47,61,53,29 is in order");
+        assert_eq!(lines[4], "61,13,29 breaks 29|13, and should be 61,29,13");
+    }
 }
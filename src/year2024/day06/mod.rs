@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::{fs, io::Write, thread};
 
 pub mod task1 {
     use super::count_distinct_points;
@@ -17,178 +17,259 @@ pub mod task2 {
 }
 
 fn count_distinct_points(file: &str) -> u128 {
-    let path: Path = file.to_string().into();
+    let lab = Lab::from_file(file);
 
-    let agent_inital = (path.agent.x, path.agent.y);
-
-    let mut visited = path.collect::<HashSet<_>>();
-    visited.insert(agent_inital);
-
-    visited.len() as u128
+    lab.visited_cells().len() as u128
 }
 
 fn find_loops(file: &str) -> u128 {
-    let initial_path = Path::from(file.to_string());
-
-    let map_width = initial_path.map_size.0;
-    let map_height = initial_path.map_size.1;
-
-    let mut run_path = initial_path.clone();
-    while let Some(_) = run_path.next() {}
-    let visited = run_path.visited;
-
-    // For each point visited, try adding an obstacle in front of the agent and see if it creates a loop
-    let effective_new_obstacles = visited
-        .into_iter()
-        .map(|agent| agent.direction.next_pos(agent.x, agent.y))
-        .filter(|(x, y)| *x >= 0 && *x < map_width && *y >= 0 && *y < map_height)
-        .filter(|(x, y)| !initial_path.obstacles.contains(&(*x as usize, *y as usize)))
-        .map(|(x, y)| (x as usize, y as usize))
-        // Collect to dedup
-        .collect::<HashSet<_>>();
-
-    effective_new_obstacles
-        .into_iter()
-        .filter(|(x, y)| {
-            let mut path: Path = initial_path.clone();
-            path.obstacles.insert((*x, *y));
-
-            while let Some(_) = path.next() {}
-
-            path.in_loop
-        })
-        .count() as u128
+    let lab = Lab::from_file(file);
+
+    lab.loop_positions().len() as u128
 }
 
-#[derive(Clone, Eq, PartialEq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 enum Direction {
     Up,
+    Right,
     Down,
     Left,
-    Right,
 }
 
-impl Direction {
-    fn next_pos(&self, x: usize, y: usize) -> (isize, isize) {
-        let x = x as isize;
-        let y = y as isize;
-        match self {
-            Direction::Up => (x, y - 1),
-            Direction::Down => (x, y + 1),
-            Direction::Left => (x - 1, y),
-            Direction::Right => (x + 1, y),
-        }
-    }
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Right,
+    Direction::Down,
+    Direction::Left,
+];
 
-    fn rotate(&self) -> Self {
-        match self {
-            Direction::Up => Direction::Right,
-            Direction::Right => Direction::Down,
-            Direction::Down => Direction::Left,
-            Direction::Left => Direction::Up,
-        }
+impl Direction {
+    fn rotate(self) -> Self {
+        DIRECTIONS[(self as usize + 1) % 4]
     }
 }
 
-#[derive(Clone, Eq, PartialEq, Hash)]
-struct Agent {
-    x: usize,
-    y: usize,
+// A guard's position and heading packed into one index, for the jump table
+type State = usize;
+
+#[derive(Clone, Debug)]
+struct Lab {
+    width: usize,
+    height: usize,
+    map: Vec<char>,
+    start: usize,
     direction: Direction,
+    // Where the guard next turns from each state, or None if they walk off the map
+    jumps: Vec<Option<State>>,
 }
 
-#[derive(Clone)]
-struct Path {
-    agent: Agent,
-    obstacles: HashSet<(usize, usize)>,
-    map_size: (isize, isize),
-    visited: HashSet<Agent>,
-    in_loop: bool,
-}
+impl Lab {
+    fn from_file(file: &str) -> Self {
+        let content = fs::read_to_string(file).unwrap();
+
+        let width = content.lines().next().unwrap().len();
+        let map: Vec<char> = content.lines().flat_map(|line| line.chars()).collect();
+        let height = map.len() / width;
+
+        let (start, direction) = map
+            .iter()
+            .enumerate()
+            .find_map(|(i, c)| match c {
+                '^' => Some((i, Direction::Up)),
+                '>' => Some((i, Direction::Right)),
+                'v' => Some((i, Direction::Down)),
+                '<' => Some((i, Direction::Left)),
+                _ => None,
+            })
+            .expect("No guard on the map");
+
+        let mut lab = Lab {
+            width,
+            height,
+            map,
+            start,
+            direction,
+            jumps: vec![],
+        };
+        lab.jumps = lab.build_jumps();
+
+        lab
+    }
 
-type FilePath = String;
-impl From<FilePath> for Path {
-    fn from(file_path: FilePath) -> Self {
-        let content = std::fs::read_to_string(file_path).unwrap();
+    fn state(cell: usize, direction: Direction) -> State {
+        cell * 4 + direction as usize
+    }
 
-        let map_width = content.clone().lines().next().unwrap().len() as isize;
-        let map_height = content.clone().lines().count() as isize;
-        let map_size = (map_width, map_height);
+    fn unpack(state: State) -> (usize, Direction) {
+        (state / 4, DIRECTIONS[state % 4])
+    }
 
-        let mut obstacles = HashSet::new();
-        let mut agent = None;
+    fn step(&self, cell: usize, direction: Direction) -> Option<usize> {
+        let (x, y) = (cell % self.width, cell / self.width);
 
-        for (y, line) in content.lines().enumerate() {
-            for (x, c) in line.chars().enumerate() {
-                match c {
-                    '#' => {
-                        obstacles.insert((x, y));
-                    }
-                    '^' => {
-                        agent = Some(Agent {
-                            x,
-                            y,
-                            direction: Direction::Up,
-                        });
+        match direction {
+            Direction::Up if y > 0 => Some(cell - self.width),
+            Direction::Right if x + 1 < self.width => Some(cell + 1),
+            Direction::Down if y + 1 < self.height => Some(cell + self.width),
+            Direction::Left if x > 0 => Some(cell - 1),
+            _ => None,
+        }
+    }
+
+    fn is_obstacle(&self, cell: usize) -> bool {
+        self.map[cell] == '#'
+    }
+
+    fn build_jumps(&self) -> Vec<Option<State>> {
+        let mut jumps = vec![None; self.map.len() * 4];
+
+        // Each state's jump is the same as the one from the cell ahead, so cells are
+        // filled in starting from the edge the guard is walking towards
+        for direction in DIRECTIONS {
+            let cells: Box<dyn Iterator<Item = usize>> = match direction {
+                Direction::Up | Direction::Left => Box::new(0..self.map.len()),
+                Direction::Down | Direction::Right => Box::new((0..self.map.len()).rev()),
+            };
+
+            for cell in cells {
+                jumps[Self::state(cell, direction)] = match self.step(cell, direction) {
+                    None => None,
+                    Some(ahead) if self.is_obstacle(ahead) => {
+                        Some(Self::state(cell, direction.rotate()))
                     }
-                    _ => {}
-                }
+                    Some(ahead) => jumps[Self::state(ahead, direction)],
+                };
             }
         }
 
-        let agent = agent.unwrap();
-
-        let mut visited = HashSet::new();
-        visited.insert(agent.clone());
+        jumps
+    }
 
-        Self {
-            agent,
-            obstacles,
-            map_size,
-            visited,
-            in_loop: false,
+    // How many steps ahead `target` is when walking in a straight line, if it's ahead at all
+    fn distance_ahead(&self, cell: usize, direction: Direction, target: usize) -> Option<usize> {
+        let (x, y) = (cell % self.width, cell / self.width);
+        let (target_x, target_y) = (target % self.width, target / self.width);
+
+        match direction {
+            Direction::Up if x == target_x && target_y <= y => Some(y - target_y),
+            Direction::Right if y == target_y && target_x >= x => Some(target_x - x),
+            Direction::Down if x == target_x && target_y >= y => Some(target_y - y),
+            Direction::Left if y == target_y && target_x <= x => Some(x - target_x),
+            _ => None,
         }
     }
-}
-
-impl Iterator for Path {
-    type Item = (usize, usize);
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let agent = &mut self.agent;
+    fn visited_cells(&self) -> Vec<usize> {
+        let mut visited = vec![false; self.map.len()];
+        let (mut cell, mut direction) = (self.start, self.direction);
+        visited[cell] = true;
+
+        while let Some(ahead) = self.step(cell, direction) {
+            if self.is_obstacle(ahead) {
+                direction = direction.rotate();
+            } else {
+                cell = ahead;
+                visited[cell] = true;
+            }
+        }
 
-        self.visited.insert(agent.clone());
+        (0..self.map.len()).filter(|&cell| visited[cell]).collect()
+    }
 
-        let (next_x, next_y): (isize, isize) = agent.direction.next_pos(agent.x, agent.y);
+    // Follows the guard from turn to turn with one extra obstacle, which can only cut a
+    // straight stretch short. Visiting the same turn twice means the guard is stuck
+    fn loops_with(&self, obstacle: usize, seen: &mut [u32], stamp: u32) -> bool {
+        let mut state = Self::state(self.start, self.direction);
 
-        if next_x < 0
-            || next_x >= self.map_size.0 as isize
-            || next_y < 0
-            || next_y >= self.map_size.1 as isize
-        {
-            return None;
+        loop {
+            if seen[state] == stamp {
+                return true;
+            }
+            seen[state] = stamp;
+
+            let (cell, direction) = Self::unpack(state);
+            let next = self.jumps[state];
+
+            let stretch = match next {
+                Some(turn) => self.distance_ahead(cell, direction, Self::unpack(turn).0),
+                None => Some(usize::MAX),
+            };
+
+            state = match self.distance_ahead(cell, direction, obstacle) {
+                Some(distance) if distance > 0 && Some(distance) <= stretch => {
+                    let before = self
+                        .step(obstacle, DIRECTIONS[(direction as usize + 2) % 4])
+                        .unwrap();
+                    Self::state(before, direction.rotate())
+                }
+                _ => match next {
+                    Some(turn) => turn,
+                    None => return false,
+                },
+            };
         }
+    }
 
-        if self.obstacles.contains(&(next_x as usize, next_y as usize)) {
-            self.agent.direction = agent.direction.rotate();
-            return self.next();
-        }
+    // Every cell where one more obstacle traps the guard, as (x, y) in reading order.
+    // Only cells on the original route can change it, and they are split between threads
+    fn loop_positions(&self) -> Vec<(usize, usize)> {
+        let candidates: Vec<usize> = self
+            .visited_cells()
+            .into_iter()
+            .filter(|&cell| cell != self.start)
+            .collect();
+
+        let threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+        let chunk_size = candidates.len().div_ceil(threads).max(1);
+
+        let mut positions: Vec<usize> = thread::scope(|scope| {
+            let workers: Vec<_> = candidates
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        let mut seen = vec![0; self.map.len() * 4];
+
+                        chunk
+                            .iter()
+                            .zip(1..)
+                            .filter(|(&cell, stamp)| self.loops_with(cell, &mut seen, *stamp))
+                            .map(|(&cell, _)| cell)
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().unwrap())
+                .collect()
+        });
+
+        positions.sort();
+        positions
+            .into_iter()
+            .map(|cell| (cell % self.width, cell / self.width))
+            .collect()
+    }
+}
 
-        agent.x = next_x as usize;
-        agent.y = next_y as usize;
+// Draws the map with an O wherever a new obstacle would cause a loop
+pub fn render_loops(file: &str, mut output: impl Write) {
+    let lab = Lab::from_file(file);
 
-        if self.visited.contains(agent) {
-            self.in_loop = true;
-            return None;
-        }
+    let mut map = lab.map.clone();
+    for (x, y) in lab.loop_positions() {
+        map[y * lab.width + x] = 'O';
+    }
 
-        Some((agent.x, agent.y))
+    for row in map.chunks(lab.width) {
+        writeln!(output, "{}", row.iter().collect::<String>()).unwrap();
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{count_distinct_points, find_loops};
+    use super::*;
 
     #[test]
     fn test_distinct_points() {
@@ -201,4 +282,35 @@ mod tests {
         let file = "resources/2024/day06/test_input.txt";
         assert_eq!(find_loops(file), 6);
     }
+
+    #[test]
+    fn test_loop_positions() {
+        let lab = Lab::from_file("resources/2024/day06/test_input.txt");
+
+        assert_eq!(
+            lab.loop_positions(),
+            [(3, 6), (6, 7), (7, 7), (1, 8), (3, 8), (7, 9)]
+        );
+    }
+
+    #[test]
+    fn test_jumps() {
+        let lab = Lab::from_file("resources/2024/day06/test_input.txt");
+        let start = Lab::state(lab.start, Direction::Up);
+
+        // The guard walks up from (4, 6) to (4, 1), then right to (8, 1)
+        let first_turn = lab.jumps[start].unwrap();
+        assert_eq!(first_turn, Lab::state(14, Direction::Right));
+        assert_eq!(lab.jumps[first_turn], Some(Lab::state(18, Direction::Down)));
+    }
+
+    #[test]
+    fn test_render_loops() {
+        let mut output = vec![];
+        render_loops("resources/2024/day06/test_input.txt", &mut output);
+        let output = String::from_utf8(output).unwrap();
+
+        assert_eq!(output.matches('O').count(), 6);
+        assert_eq!(output.lines().nth(6).unwrap(), ".#.O^.....");
+    }
 }
@@ -1,81 +1,202 @@
-use std::fs;
+use std::{fmt::Display, fs, io::Write, str::FromStr};
 
 pub mod task1 {
-    use super::sum_possible_equations;
+    use super::{sum_possible_equations, Operator};
 
     pub fn ans() -> u128 {
-        sum_possible_equations("resources/2024/day07/input.txt", false)
+        sum_possible_equations(
+            "resources/2024/day07/input.txt",
+            &[Operator::Add, Operator::Multiply],
+        )
     }
 }
 
 pub mod task2 {
-    use super::sum_possible_equations;
+    use super::{sum_possible_equations, Operator};
 
     pub fn ans() -> u128 {
-        sum_possible_equations("resources/2024/day07/input.txt", true)
+        sum_possible_equations(
+            "resources/2024/day07/input.txt",
+            &[Operator::Add, Operator::Multiply, Operator::Concat(10)],
+        )
     }
 }
 
-fn sum_possible_equations(file: &str, use_concat: bool) -> u128 {
-    let contents = fs::read_to_string(file).unwrap();
+// Operators are always evaluated left to right, ignoring precedence
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    // Appends the digits of the right operand in the given base, only defined for
+    // non-negative operands
+    Concat(u128),
+    // Can always be undone, so it never prunes the search
+    Xor,
+}
 
-    contents
-        .lines()
-        .map(|line| line_value(line, use_concat))
-        .sum::<u128>()
+impl Operator {
+    // Finds the left operand that gives `result` with this right operand, if there is one.
+    // Multiplying by zero can't be undone, but puzzle inputs never contain zeroes
+    fn undo(self, result: i128, b: i128) -> Option<i128> {
+        match self {
+            Operator::Add => result.checked_sub(b),
+            Operator::Subtract => result.checked_add(b),
+            Operator::Multiply => (b != 0 && result % b == 0).then(|| result / b),
+            Operator::Concat(base) => {
+                if result < 0 || b < 0 {
+                    return None;
+                }
+                let base = base as i128;
+                let mut shift = base;
+                while shift <= b {
+                    shift *= base;
+                }
+                (result % shift == b).then(|| result / shift)
+            }
+            Operator::Xor => Some(result ^ b),
+        }
+    }
 }
 
-fn line_value(line: &str, use_concat: bool) -> u128 {
-    let (total, operands) = line.split_once(": ").unwrap();
+impl Display for Operator {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Operator::Add => write!(f, "+"),
+            Operator::Subtract => write!(f, "-"),
+            Operator::Multiply => write!(f, "*"),
+            Operator::Concat(10) => write!(f, "||"),
+            Operator::Concat(base) => write!(f, "||{base}"),
+            Operator::Xor => write!(f, "^"),
+        }
+    }
+}
 
-    let total = total.parse::<u128>().unwrap();
-    let operands = operands
-        .split(" ")
-        .map(|x| x.parse::<u128>().unwrap())
-        .collect::<Vec<u128>>();
+impl FromStr for Operator {
+    type Err = String;
 
-    if is_valid_equation(total, operands[0], &operands[1..], use_concat) {
-        return total;
-    } else {
-        return 0;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "+" => Ok(Operator::Add),
+            "-" => Ok(Operator::Subtract),
+            "*" => Ok(Operator::Multiply),
+            "||" => Ok(Operator::Concat(10)),
+            "^" => Ok(Operator::Xor),
+            _ => match s.strip_prefix("||").map(|base| base.parse()) {
+                Some(Ok(base)) if base >= 2 => Ok(Operator::Concat(base)),
+                _ => Err(format!("Unknown operator {s}")),
+            },
+        }
     }
 }
 
-fn is_valid_equation(total: u128, a: u128, operands: &[u128], use_concat: bool) -> bool {
-    let b = operands[0];
+// Intermediate results can go below zero, so values are signed
+struct Equation {
+    total: i128,
+    operands: Vec<i128>,
+}
 
-    if operands.len() == 1 {
-        let is_addition = a + b == total;
-        let is_multiplication = a * b == total;
-        let is_concat = format!("{}{}", a, b).parse::<u128>().unwrap() == total;
+impl Equation {
+    // The operators between each pair of operands that make the equation true
+    fn solve(&self, operators: &[Operator]) -> Option<Vec<Operator>> {
+        let mut chosen = vec![];
 
-        return is_addition || is_multiplication || (use_concat && is_concat);
+        solve_from_right(self.total, &self.operands, operators, &mut chosen).then(|| {
+            chosen.reverse();
+            chosen
+        })
     }
 
-    let sum_operand = a + b;
-    let sum_possible = sum_operand <= total;
+    fn witness(&self, operators: &[Operator]) -> Option<String> {
+        let chosen = self.solve(operators)?;
+
+        let mut witness = self.operands[0].to_string();
+        for (operator, operand) in chosen.iter().zip(&self.operands[1..]) {
+            witness += &format!(" {operator} {operand}");
+        }
+        Some(witness)
+    }
+}
 
-    let mul_operand = a * b;
-    let mul_possible = mul_operand <= total;
+// Works backwards from the total, undoing the last operator each time. Most operators
+// can only be undone for a few results, which prunes the search far more than trying
+// every operator from the left
+fn solve_from_right(
+    total: i128,
+    operands: &[i128],
+    operators: &[Operator],
+    chosen: &mut Vec<Operator>,
+) -> bool {
+    let (&last, rest) = operands.split_last().unwrap();
 
-    let concat_operand = format!("{}{}", a, b).parse::<u128>().unwrap();
-    let concat_possible = concat_operand <= total;
+    if rest.is_empty() {
+        return total == last;
+    }
+
+    for &operator in operators {
+        if let Some(previous) = operator.undo(total, last) {
+            chosen.push(operator);
+            if solve_from_right(previous, rest, operators, chosen) {
+                return true;
+            }
+            chosen.pop();
+        }
+    }
+
+    false
+}
+
+fn parse_file(file: &str) -> Vec<Equation> {
+    let contents = fs::read_to_string(file).unwrap();
+
+    contents
+        .lines()
+        .map(|line| {
+            let (total, operands) = line.split_once(": ").unwrap();
+
+            Equation {
+                total: total.parse().unwrap(),
+                operands: operands.split(' ').map(|x| x.parse().unwrap()).collect(),
+            }
+        })
+        .collect()
+}
 
-    (sum_possible && is_valid_equation(total, sum_operand, &operands[1..], use_concat))
-        || (mul_possible && is_valid_equation(total, mul_operand, &operands[1..], use_concat))
-        || (use_concat
-            && (concat_possible
-                && is_valid_equation(total, concat_operand, &operands[1..], use_concat)))
+fn sum_possible_equations(file: &str, operators: &[Operator]) -> u128 {
+    parse_file(file)
+        .into_iter()
+        .filter(|equation| equation.solve(operators).is_some())
+        .map(|equation| u128::try_from(equation.total).unwrap())
+        .sum()
+}
+
+pub fn explain_equations(file: &str, operators: &[Operator], mut output: impl Write) {
+    let mut calibration = 0;
+
+    for equation in parse_file(file) {
+        match equation.witness(operators) {
+            Some(witness) => {
+                calibration += equation.total;
+                writeln!(output, "{} = {witness}", equation.total).unwrap();
+            }
+            None => writeln!(output, "{} has no solution", equation.total).unwrap(),
+        }
+    }
+
+    writeln!(output, "Total calibration result: {calibration}").unwrap();
 }
 
 #[cfg(test)]
 mod tests {
-    use super::sum_possible_equations;
+    use super::*;
+
+    const BASIC: [Operator; 2] = [Operator::Add, Operator::Multiply];
+    const WITH_CONCAT: [Operator; 3] = [Operator::Add, Operator::Multiply, Operator::Concat(10)];
 
     #[test]
     fn test_sum_possible_equations() {
         assert_eq!(
-            sum_possible_equations("resources/2024/day07/test_input.txt", false),
+            sum_possible_equations("resources/2024/day07/test_input.txt", &BASIC),
             3749
         );
     }
@@ -88,7 +209,7 @@ mod tests {
     #[test]
     fn test_sum_possible_equations_with_concat() {
         assert_eq!(
-            sum_possible_equations("resources/2024/day07/test_input.txt", true),
+            sum_possible_equations("resources/2024/day07/test_input.txt", &WITH_CONCAT),
             11387
         );
     }
@@ -97,4 +218,65 @@ mod tests {
     fn bench_sum_possible_equations_with_concat(b: &mut crate::Bencher) {
         b.iter(|| test_sum_possible_equations_with_concat());
     }
+
+    #[test]
+    fn test_witness() {
+        let equation = Equation {
+            total: 3267,
+            operands: vec![81, 40, 27],
+        };
+        // 81 + 40 * 27 works too, but the last operator tried first wins
+        assert_eq!(equation.witness(&BASIC).unwrap(), "81 * 40 + 27");
+
+        let equation = Equation {
+            total: 7290,
+            operands: vec![6, 8, 6, 15],
+        };
+        assert_eq!(equation.witness(&BASIC), None);
+        assert_eq!(equation.witness(&WITH_CONCAT).unwrap(), "6 * 8 || 6 * 15");
+    }
+
+    #[test]
+    fn test_other_operators() {
+        let operators: Vec<Operator> = "- ^ ||2"
+            .split(' ')
+            .map(|operator| operator.parse().unwrap())
+            .collect();
+        assert_eq!(
+            operators,
+            [Operator::Subtract, Operator::Xor, Operator::Concat(2)]
+        );
+
+        // 10 - 3 - 5 = 0b10, then appending 0b11 gives 0b1011
+        let equation = Equation {
+            total: 11,
+            operands: vec![10, 3, 5, 3],
+        };
+        assert_eq!(equation.witness(&operators).unwrap(), "10 - 3 - 5 ||2 3");
+
+        let equation = Equation {
+            total: 6,
+            operands: vec![3, 5],
+        };
+        assert_eq!(equation.witness(&operators).unwrap(), "3 ^ 5");
+        assert!("|8".parse::<Operator>().is_err());
+    }
+
+    #[test]
+    fn test_negative_intermediates() {
+        let operators = [Operator::Add, Operator::Subtract];
+
+        let equation = Equation {
+            total: 5,
+            operands: vec![12, 10, 3],
+        };
+        assert_eq!(equation.witness(&operators).unwrap(), "12 - 10 + 3");
+
+        // Goes through -7 on the way
+        let equation = Equation {
+            total: 5,
+            operands: vec![3, 10, 12],
+        };
+        assert_eq!(equation.witness(&operators).unwrap(), "3 - 10 + 12");
+    }
 }
@@ -1,186 +1,171 @@
+use std::{cmp::Reverse, collections::BinaryHeap, fmt::Display, fs};
+
 pub mod task1 {
-    use super::sorted_checksum;
+    use super::{compacted_checksum, Strategy};
 
     pub fn ans() -> u128 {
-        sorted_checksum("resources/2024/day09/input.txt")
+        compacted_checksum("resources/2024/day09/input.txt", Strategy::Blocks)
     }
 }
 
 pub mod task2 {
-    use super::defrag_checksum;
+    use super::{compacted_checksum, Strategy};
 
     pub fn ans() -> u128 {
-        defrag_checksum("resources/2024/day09/input.txt")
+        compacted_checksum("resources/2024/day09/input.txt", Strategy::Files)
     }
 }
 
-fn sorted_checksum(file: &str) -> u128 {
-    let content: &mut [u8] = &mut std::fs::read_to_string(file)
-        .unwrap()
-        .trim()
-        .chars()
-        .map(|c| c.to_digit(10).unwrap() as u8)
-        .collect::<Vec<u8>>();
-
-    let length = content.len();
-
-    let mut end_is_data = true;
-    let mut start_is_data = true;
-
-    let mut left_pointer = 0;
-    let mut left_block_pos = 0;
-    let mut right_pointer = if length % 2 != 0 {
-        length - 1
-    } else {
-        length - 2
-    };
-
-    let file_id = |pointer: usize| (pointer / 2) as u128;
-
-    let mut checksum: u128 = 0;
-
-    while left_pointer <= right_pointer {
-        let left = content[left_pointer];
-        let right = content[right_pointer];
-
-        if !end_is_data || right == 0 {
-            // Skip empty fields on right, or files already moved
-            right_pointer -= 1;
-            end_is_data = !end_is_data;
-        } else if left == 0 {
-            // Skip fields already accounted for on left
-            left_pointer += 1;
-            start_is_data = !start_is_data;
-        } else if start_is_data {
-            // Add checksum for left field and move on
-            checksum += left_block_pos * file_id(left_pointer);
-            left_block_pos += 1;
-
-            let new_left = left - 1;
-            content[left_pointer] = new_left;
-        } else {
-            // Remaining case where left pointer is empty and right pointer is data
-            content[right_pointer] -= 1;
-            content[left_pointer] -= 1;
-
-            checksum += file_id(right_pointer) * left_block_pos;
-            left_block_pos += 1;
-        }
-    }
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Strategy {
+    // Moves one block at a time, splitting files to fill every gap
+    Blocks,
+    // Moves whole files into the leftmost gap big enough for them
+    Files,
+}
 
-    checksum
+// A run of blocks belonging to one file
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Extent {
+    id: usize,
+    start: usize,
+    length: usize,
 }
 
-#[derive(Debug)]
-enum Block {
-    Empty(u8),
-    Data(u8, usize),
+// Spans in the disk map are a single digit long, and moving files only makes them shorter
+const MAX_SPAN: usize = 9;
+
+#[derive(Clone, Debug)]
+struct Disk {
+    size: usize,
+    // The first extent of each file sits at the index of its id
+    extents: Vec<Extent>,
+    // Starts of the free spans of each length, leftmost first
+    free: [BinaryHeap<Reverse<usize>>; MAX_SPAN + 1],
 }
 
-impl Block {
-    fn size(&self) -> u8 {
-        match self {
-            Block::Empty(size) => *size,
-            Block::Data(size, _) => *size,
-        }
+impl Disk {
+    fn from_file(file: &str) -> Self {
+        let content = fs::read_to_string(file).unwrap();
+        Self::from_map(content.trim())
     }
 
-    fn id(&self) -> Option<usize> {
-        match self {
-            Block::Empty(_) => None,
-            Block::Data(_, id) => Some(*id),
+    fn from_map(map: &str) -> Self {
+        let mut disk = Disk {
+            size: 0,
+            extents: vec![],
+            free: Default::default(),
+        };
+
+        for (i, c) in map.chars().enumerate() {
+            let length = c.to_digit(10).unwrap() as usize;
+
+            if i % 2 == 0 {
+                disk.extents.push(Extent {
+                    id: i / 2,
+                    start: disk.size,
+                    length,
+                });
+            } else {
+                disk.add_span(disk.size, length);
+            }
+            disk.size += length;
         }
-    }
 
-    fn is_empty(&self) -> bool {
-        matches!(self, Block::Empty(_))
+        disk
     }
-}
 
-fn defrag_checksum(file: &str) -> u128 {
-    let content: &mut [u8] = &mut std::fs::read_to_string(file)
-        .unwrap()
-        .trim()
-        .chars()
-        .map(|c| c.to_digit(10).unwrap() as u8)
-        .collect::<Vec<u8>>();
-
-    let mut blocks = content
-        .into_iter()
-        .enumerate()
-        .fold(
-            (Vec::new(), true),
-            |(mut blocks, is_file), (index, block_size)| {
-                if is_file {
-                    blocks.push(Block::Data(*block_size, index / 2));
-                } else {
-                    blocks.push(Block::Empty(*block_size));
-                }
-
-                (blocks, !is_file)
-            },
-        )
-        .0;
-
-    let length = blocks.len();
-
-    // Defrag
-    let mut file_block_index = length;
-    while file_block_index > 0 {
-        file_block_index -= 1;
+    fn add_span(&mut self, start: usize, length: usize) {
+        if length > 0 {
+            self.free[length].push(Reverse(start));
+        }
+    }
 
-        if blocks[file_block_index].is_empty() {
-            continue;
+    // Removes the leftmost free span with room for `length` blocks, as long as it starts
+    // before `before`. Space freed by moving a file is never needed again, as every file
+    // still to move is further left
+    fn take_span(&mut self, length: usize, before: usize) -> Option<(usize, usize)> {
+        let (start, span_length) = (length..=MAX_SPAN)
+            .filter_map(|span_length| {
+                let Reverse(start) = self.free[span_length].peek()?;
+                Some((*start, span_length))
+            })
+            .min()?;
+
+        if start >= before {
+            return None;
         }
 
-        let file_block = &blocks[file_block_index];
-        let file_size = file_block.size();
-        let file_id = file_block.id().unwrap();
+        self.free[span_length].pop();
+        Some((start, span_length))
+    }
 
-        let empty_block_index = (0..file_block_index)
-            .filter(|index| blocks[*index].is_empty())
-            .find(|index| {
-                let block = &blocks[*index];
-                let block_size = block.size();
+    fn compact(&mut self, strategy: Strategy) {
+        for id in (0..self.extents.len()).rev() {
+            let mut file = self.extents[id];
+
+            match strategy {
+                Strategy::Blocks => {
+                    // Blocks leave from the end of the file, so what's left still starts
+                    // in the same place
+                    while file.length > 0 {
+                        let Some((start, span_length)) = self.take_span(1, file.start) else {
+                            break;
+                        };
+
+                        let moved = span_length.min(file.length);
+                        file.length -= moved;
+                        self.extents.push(Extent {
+                            id,
+                            start,
+                            length: moved,
+                        });
+                        self.add_span(start + moved, span_length - moved);
+                    }
+                }
+                Strategy::Files => {
+                    if let Some((start, span_length)) = self.take_span(file.length, file.start) {
+                        file.start = start;
+                        self.add_span(start + file.length, span_length - file.length);
+                    }
+                }
+            }
 
-                block_size >= file_size
-            });
+            self.extents[id] = file;
+        }
+    }
 
-        if let Some(empty_block_index) = empty_block_index {
-            let empty_block = &blocks[empty_block_index];
-            let empty_block_size = empty_block.size();
+    fn checksum(&self) -> u128 {
+        self.extents
+            .iter()
+            .map(|extent| {
+                (extent.start..extent.start + extent.length)
+                    .map(|position| (position * extent.id) as u128)
+                    .sum::<u128>()
+            })
+            .sum()
+    }
+}
 
-            if empty_block_size == file_size {
-                blocks[empty_block_index] = Block::Data(file_size, file_id);
-                blocks[file_block_index] = Block::Empty(empty_block_size);
-            } else {
-                let remainder = empty_block_size - file_size;
-                blocks[empty_block_index] = Block::Data(file_size, file_id);
-                blocks[file_block_index] = Block::Empty(file_size);
+// Draws each block as the last digit of its file's id, with free blocks as dots
+impl Display for Disk {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut blocks = vec!['.'; self.size];
 
-                blocks.insert(empty_block_index + 1, Block::Empty(remainder));
-                file_block_index += 1;
-            }
+        for extent in &self.extents {
+            let digit = char::from_digit((extent.id % 10) as u32, 10).unwrap();
+            blocks[extent.start..extent.start + extent.length].fill(digit);
         }
+
+        write!(f, "{}", blocks.into_iter().collect::<String>())
     }
+}
 
-    blocks
-        .into_iter()
-        .fold(
-            (0u128, 0u128),
-            |(mut checksum, mut index), block| match block {
-                Block::Empty(size) => (checksum, index + size as u128),
-                Block::Data(size, id) => {
-                    for _ in 0..size {
-                        checksum += index as u128 * id as u128;
-                        index += 1;
-                    }
+fn compacted_checksum(file: &str, strategy: Strategy) -> u128 {
+    let mut disk = Disk::from_file(file);
+    disk.compact(strategy);
 
-                    (checksum, index)
-                }
-            },
-        )
-        .0
+    disk.checksum()
 }
 
 #[cfg(test)]
@@ -189,11 +174,49 @@ mod tests {
 
     #[test]
     fn test_sorted_checksum() {
-        assert_eq!(sorted_checksum("resources/2024/day09/test_input.txt"), 1928);
+        assert_eq!(
+            compacted_checksum("resources/2024/day09/test_input.txt", Strategy::Blocks),
+            1928
+        );
     }
 
     #[test]
     fn test_defrag_checksum() {
-        assert_eq!(defrag_checksum("resources/2024/day09/test_input.txt"), 2858);
+        assert_eq!(
+            compacted_checksum("resources/2024/day09/test_input.txt", Strategy::Files),
+            2858
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        let mut disk = Disk::from_map("12345");
+        assert_eq!(disk.to_string(), "0..111....22222");
+
+        disk.compact(Strategy::Blocks);
+        assert_eq!(disk.to_string(), "022111222......");
+    }
+
+    #[test]
+    fn test_compaction_layouts() {
+        let disk = Disk::from_file("resources/2024/day09/test_input.txt");
+        assert_eq!(
+            disk.to_string(),
+            "00...111...2...333.44.5555.6666.777.888899"
+        );
+
+        let mut blocks = disk.clone();
+        blocks.compact(Strategy::Blocks);
+        assert_eq!(
+            blocks.to_string(),
+            "0099811188827773336446555566.............."
+        );
+
+        let mut files = disk.clone();
+        files.compact(Strategy::Files);
+        assert_eq!(
+            files.to_string(),
+            "00992111777.44.333....5555.6666.....8888.."
+        );
     }
 }
@@ -1,4 +1,4 @@
-use std::{collections::HashMap, fs};
+use std::{fs, io::Write, str::FromStr};
 
 use regex::Regex;
 
@@ -6,22 +6,48 @@ pub mod task1 {
     use super::hundred_second_quadrant_score;
 
     pub fn ans() -> u128 {
-        hundred_second_quadrant_score("resources/2024/day14/input.txt", 103, 101)
+        hundred_second_quadrant_score("resources/2024/day14/input.txt", None)
     }
 }
 
 pub mod task2 {
-    use super::low_entropy;
+    use super::tree_step;
 
     pub fn ans() -> u128 {
-        low_entropy("resources/2024/day14/input.txt")
+        tree_step("resources/2024/day14/input.txt", None).unwrap_or_else(|error| panic!("{error}"))
+            as u128
     }
 }
 
-fn hundred_second_quadrant_score(file: &str, height: isize, width: isize) -> u128 {
-    let robots = parse_file(file);
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Arena {
+    width: isize,
+    height: isize,
+}
 
-    quadrant_product(robots, height, width, 100)
+// The real puzzle's size, used when the input doesn't start with an `arena=WxH` line
+const PUZZLE_ARENA: Arena = Arena {
+    width: 101,
+    height: 103,
+};
+
+impl FromStr for Arena {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (width, height) = s
+            .split_once('x')
+            .ok_or_else(|| format!("Arena {s} should look like 101x103"))?;
+        let size = |size: &str| match size.parse() {
+            Ok(size) if size > 0 => Ok(size),
+            _ => Err(format!("Invalid arena size {s}")),
+        };
+
+        Ok(Arena {
+            width: size(width)?,
+            height: size(height)?,
+        })
+    }
 }
 
 #[derive(Clone)]
@@ -48,76 +74,145 @@ impl From<&str> for Robot {
 }
 
 impl Robot {
-    fn move_robot(mut self, times: isize) -> Self {
-        self.p_x += times * self.v_x;
-        self.p_y += times * self.v_y;
-
-        self
-    }
-
-    fn wrap_around(mut self, height: isize, width: isize) -> Self {
-        self.p_x = ((self.p_x % width) + width) % width;
-        self.p_y = ((self.p_y % height) + height) % height;
+    fn position_at(&self, step: usize, arena: Arena) -> (isize, isize) {
+        let step = step as isize;
 
-        self
+        (
+            (self.p_x + step * self.v_x).rem_euclid(arena.width),
+            (self.p_y + step * self.v_y).rem_euclid(arena.height),
+        )
     }
 }
 
-fn parse_file(file: &str) -> Vec<Robot> {
+// An arena given on the command line wins over the one in the input
+fn parse_file(file: &str, arena: Option<Arena>) -> (Arena, Vec<Robot>) {
     let contents = fs::read_to_string(file).unwrap();
-
-    contents.lines().map(Robot::from).collect()
-}
-
-fn quadrant_product(robots: Vec<Robot>, height: isize, width: isize, times: isize) -> u128 {
-    let half_width = width / 2;
-    let half_height = height / 2;
-
-    let quad_counts = robots
-        .into_iter()
-        .map(|r| r.move_robot(times))
-        .map(|r| r.wrap_around(height, width))
-        .fold((0, 0, 0, 0), |acc, r| {
-            match (
-                r.p_x < half_width,
-                r.p_x > half_width,
-                r.p_y < half_height,
-                r.p_y > half_height,
-            ) {
-                (true, false, true, false) => (acc.0 + 1, acc.1, acc.2, acc.3),
-                (false, true, true, false) => (acc.0, acc.1 + 1, acc.2, acc.3),
-                (true, false, false, true) => (acc.0, acc.1, acc.2 + 1, acc.3),
-                (false, true, false, true) => (acc.0, acc.1, acc.2, acc.3 + 1),
-                _ => acc,
-            }
+    let mut lines = contents.lines().peekable();
+
+    let input_arena = lines
+        .next_if(|line| line.starts_with("arena="))
+        .map(|line| {
+            line["arena=".len()..]
+                .parse()
+                .unwrap_or_else(|error| panic!("{error}"))
         });
 
-    quad_counts.0 * quad_counts.1 * quad_counts.2 * quad_counts.3
+    let arena = arena.or(input_arena).unwrap_or(PUZZLE_ARENA);
+    (arena, lines.map(Robot::from).collect())
 }
 
-fn low_entropy(file: &str) -> u128 {
-    // Look for an image being formed by iterating a number of cycles (10_000)
-    // and then checking if the entropy is low enough to be considered an image
-    // Use the quadrant_product function as entropy measure
+fn hundred_second_quadrant_score(file: &str, arena: Option<Arena>) -> u128 {
+    let (arena, robots) = parse_file(file, arena);
 
-    let robots = parse_file(file);
+    quadrant_product(&robots, arena, 100)
+}
 
-    let mut entropies: HashMap<u128, usize> = HashMap::new();
+fn quadrant_product(robots: &[Robot], arena: Arena, step: usize) -> u128 {
+    let half_width = arena.width / 2;
+    let half_height = arena.height / 2;
 
-    for i in 0..10_000 {
-        let entropy = quadrant_product(robots.clone(), 103, 101, i as isize);
+    let mut quadrants = [0; 4];
+    for robot in robots {
+        let (x, y) = robot.position_at(step, arena);
 
-        if let None = entropies.get(&entropy) {
-            entropies.insert(entropy, i);
+        if x != half_width && y != half_height {
+            quadrants[(x > half_width) as usize + 2 * (y > half_height) as usize] += 1;
         }
     }
 
-    let index_of_low_entropy = entropies
-        .get_key_value(&entropies.keys().min().unwrap())
+    quadrants.iter().product()
+}
+
+// Sum of squared distances from the mean, scaled by the count to stay in integers
+fn spread(values: impl Iterator<Item = isize>) -> i128 {
+    let (count, sum, sum_squares) = values.fold((0, 0, 0), |(count, sum, sum_squares), value| {
+        let value = value as i128;
+        (count + 1, sum + value, sum_squares + value * value)
+    });
+
+    count * sum_squares - sum * sum
+}
+
+fn tightest_step(period: isize, spread_at: impl Fn(usize) -> i128) -> usize {
+    (0..period as usize)
+        .min_by_key(|&step| spread_at(step))
         .unwrap()
-        .1;
+}
+
+// The x positions repeat every `width` steps and the y positions every `height` steps,
+// so each axis is tightest at one point in its own cycle when the robots bunch up into
+// the picture. The step where both line up follows from the Chinese remainder theorem
+fn tree_step(file: &str, arena: Option<Arena>) -> Result<usize, String> {
+    let (arena, robots) = parse_file(file, arena);
+
+    let x_step = tightest_step(arena.width, |step| {
+        spread(robots.iter().map(|robot| robot.position_at(step, arena).0))
+    });
+    let y_step = tightest_step(arena.height, |step| {
+        spread(robots.iter().map(|robot| robot.position_at(step, arena).1))
+    });
+
+    let (width, height) = (arena.width as usize, arena.height as usize);
+    align_steps((x_step, width), (y_step, height)).ok_or_else(|| {
+        format!(
+            "The robots bunch up at step {x_step} of every {width} across and step {y_step} \
+             of every {height} down, which never happen together"
+        )
+    })
+}
+
+// Solved by sieving over one combined cycle, as the arena is small. When the sides share
+// a factor, the steps only line up if they agree modulo it
+fn align_steps((a, m): (usize, usize), (b, n): (usize, usize)) -> Option<usize> {
+    (a..num::integer::lcm(m, n))
+        .step_by(m)
+        .find(|step| step % n == b)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImageFormat {
+    Text,
+    // Plain portable bitmap, which most image viewers can open
+    Pbm,
+}
+
+// Draws the robots at a step, or at the step the tree appears if none is given
+pub fn render_robots(
+    file: &str,
+    arena: Option<Arena>,
+    step: Option<usize>,
+    format: ImageFormat,
+    mut output: impl Write,
+) {
+    let step =
+        step.unwrap_or_else(|| tree_step(file, arena).unwrap_or_else(|error| panic!("{error}")));
+    let (arena, robots) = parse_file(file, arena);
+
+    let mut occupied = vec![vec![false; arena.width as usize]; arena.height as usize];
+    for robot in &robots {
+        let (x, y) = robot.position_at(step, arena);
+        occupied[y as usize][x as usize] = true;
+    }
+
+    if format == ImageFormat::Pbm {
+        writeln!(output, "P1\n# Robots after {step} seconds").unwrap();
+        writeln!(output, "{} {}", arena.width, arena.height).unwrap();
+    }
 
-    *index_of_low_entropy as u128
+    for row in occupied {
+        let pixels: Vec<&str> = row
+            .into_iter()
+            .map(|occupied| match (format, occupied) {
+                (ImageFormat::Text, true) => "#",
+                (ImageFormat::Text, false) => ".",
+                (ImageFormat::Pbm, true) => "1",
+                (ImageFormat::Pbm, false) => "0",
+            })
+            .collect();
+
+        let separator = if format == ImageFormat::Pbm { " " } else { "" };
+        writeln!(output, "{}", pixels.join(separator)).unwrap();
+    }
 }
 
 #[cfg(test)]
@@ -127,35 +222,78 @@ mod tests {
     #[test]
     fn test_quadrant_product() {
         assert_eq!(
-            hundred_second_quadrant_score("resources/2024/day14/test_input.txt", 7, 11),
+            hundred_second_quadrant_score("resources/2024/day14/test_input.txt", None),
             12
         );
     }
 
-    // #[test]
-    // fn investigate_low_entropy() {
-    //     let robots = parse_file("resources/2024/day14/input.txt");
-
-    //     let low_entropy_time = low_entropy("resources/2024/day14/input.txt");
-
-    //     let moved_robots = robots
-    //         .into_iter()
-    //         .map(|r| r.move_robot(low_entropy_time as isize))
-    //         .map(|r| r.wrap_around(103, 101))
-    //         .map(|r| (r.p_x, r.p_y))
-    //         .collect::<std::collections::HashSet<(isize, isize)>>();
-
-    //     for y in 0..103 {
-    //         for x in 0..101 {
-    //             if moved_robots.contains(&(x, y)) {
-    //                 print!("#");
-    //             } else {
-    //                 print!(".");
-    //             }
-    //         }
-    //         println!();
-    //     }
-
-    //     assert!(false);
-    // }
+    #[test]
+    fn test_parse_arena() {
+        let (arena, robots) = parse_file("resources/2024/day14/test_input.txt", None);
+        assert_eq!(
+            arena,
+            Arena {
+                width: 11,
+                height: 7
+            }
+        );
+        assert_eq!(robots.len(), 12);
+
+        let (arena, _) = parse_file("resources/2024/day14/test_input.txt", "5x3".parse().ok());
+        assert_eq!(arena.width * arena.height, 15);
+
+        assert!("11x0".parse::<Arena>().is_err());
+        assert!("11".parse::<Arena>().is_err());
+    }
+
+    #[test]
+    fn test_tree_step() {
+        // The robot followed in the puzzle is at (1, 3) after 5 seconds
+        let robot = Robot::from("p=2,4 v=2,-3");
+        let arena = "11x7".parse().unwrap();
+        assert_eq!(robot.position_at(5, arena), (1, 3));
+
+        // Robots that all meet at (1, 1) at step 60 of the 77 step cycle
+        let file = "resources/2024/day14/test_input_2.txt";
+        assert_eq!(tree_step(file, None), Ok(60));
+
+        let mut output = vec![];
+        render_robots(file, None, None, ImageFormat::Pbm, &mut output);
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.starts_with("P1\n# Robots after 60 seconds\n11 7\n0 0 0 0"));
+        assert_eq!(output.lines().nth(4).unwrap(), "0 1 0 0 0 0 0 0 0 0 0");
+    }
+
+    #[test]
+    fn test_render_robots() {
+        let mut output = vec![];
+        render_robots(
+            "resources/2024/day14/test_input.txt",
+            None,
+            Some(100),
+            ImageFormat::Text,
+            &mut output,
+        );
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "......#..#.\n\
+             ...........\n\
+             #..........\n\
+             .##........\n\
+             .....#.....\n\
+             ...##......\n\
+             .#....#....\n"
+        );
+    }
+
+    #[test]
+    fn test_align_steps() {
+        assert_eq!(align_steps((60 % 11, 11), (60 % 7, 7)), Some(60));
+
+        // Sides of 4 and 6 share a factor of 2, so only steps that agree modulo 2 meet
+        assert_eq!(align_steps((1, 4), (3, 6)), Some(9));
+        assert_eq!(align_steps((0, 4), (1, 6)), None);
+    }
 }
@@ -1,5 +1,10 @@
-use core::panic;
-use std::{collections::HashSet, fs};
+use std::{
+    fmt::Display,
+    fs,
+    io::{BufRead, Write},
+};
+
+use crate::grid::Grid;
 
 pub mod task1 {
     use super::sum_pushed_coordinates;
@@ -15,7 +20,7 @@ pub mod task2 {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 enum Direction {
     Up,
     Down,
@@ -23,219 +28,440 @@ enum Direction {
     Right,
 }
 
-fn parse_map(
-    map: &str,
-    expand: bool,
-) -> (
-    (isize, isize),
-    HashSet<(isize, isize)>,
-    HashSet<(isize, isize)>,
-) {
-    let mut walls: HashSet<(isize, isize)> = HashSet::new();
-    let mut boxes: HashSet<(isize, isize)> = HashSet::new();
-
-    let mut robot: (isize, isize) = (0, 0);
-
-    map.lines()
-        .enumerate()
-        .flat_map(|(y, line)| {
-            line.chars()
-                .enumerate()
-                .map(move |(x, c)| (x as isize, y as isize, c))
-        })
-        .map(|(x, y, c)| if expand { (2 * x, y, c) } else { (x, y, c) })
-        .for_each(|(x, y, c)| match c {
-            '#' => {
-                walls.insert((x, y));
-                if expand {
-                    walls.insert((x + 1, y));
-                }
-            }
-            'O' => {
-                boxes.insert((x, y));
-            }
-            '@' => {
-                robot = (x, y);
-            }
-            '.' => (),
-            _ => panic!("Invalid character"),
-        });
+impl Direction {
+    fn delta(self) -> (isize, isize) {
+        match self {
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+        }
+    }
+}
 
-    (robot, boxes, walls)
+impl Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let arrow = match self {
+            Direction::Up => '^',
+            Direction::Down => 'v',
+            Direction::Left => '<',
+            Direction::Right => '>',
+        };
+        write!(f, "{arrow}")
+    }
 }
 
-fn sum_pushed_coordinates(file: &str, expand: bool) -> u128 {
-    let contents = fs::read_to_string(file).unwrap();
-    let mut split = contents.split("\n\n");
-    let map = split.next().unwrap();
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Tile {
+    Empty,
+    Wall,
+    Box,
+    // The two halves of a box in the widened warehouse
+    BoxLeft,
+    BoxRight,
+}
 
-    let (mut robot, mut boxes, walls) = parse_map(map, expand);
+// Enough to put a move back: where the robot was, and where the boxes it pushed ended up
+#[derive(Clone, Debug)]
+struct Move {
+    direction: Direction,
+    robot: (usize, usize),
+    pushed: Vec<(usize, usize)>,
+}
 
-    let mut commands = split
-        .next()
-        .unwrap()
-        .lines()
-        .flat_map(|line| line.chars())
-        .map(|char| match char {
-            '^' => Direction::Up,
-            'v' => Direction::Down,
-            '<' => Direction::Left,
-            '>' => Direction::Right,
-            _ => panic!("Invalid direction"),
-        });
+#[derive(Debug, PartialEq)]
+enum WarehouseError {
+    SplitBox((usize, usize)),
+    RobotInside((usize, usize)),
+    BoxInWall,
+    LostBox,
+}
 
-    while let Some(command) = commands.next() {
-        make_move(&command, &mut robot, &mut boxes, &walls, expand);
+impl Display for WarehouseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            WarehouseError::SplitBox(position) => {
+                write!(f, "half of a wide box is missing at {position:?}")
+            }
+            WarehouseError::RobotInside(position) => {
+                write!(f, "the robot is inside a wall or box at {position:?}")
+            }
+            WarehouseError::BoxInWall => write!(f, "a box has been pushed into a wall"),
+            WarehouseError::LostBox => write!(f, "a box has disappeared"),
+        }
     }
+}
 
-    boxes
-        .into_iter()
-        .map(|(x, y)| x + (100 * y))
-        .map(|x| x as u128)
-        .sum()
+#[derive(Clone, Debug)]
+struct Warehouse {
+    tiles: Grid<Tile>,
+    robot: (usize, usize),
+    // Counted when the warehouse is parsed, and neither should ever change
+    walls: usize,
+    boxes: usize,
+    // Running totals kept up to date by `shift`, so each move can be checked cheaply
+    wall_tiles: usize,
+    box_tiles: usize,
+    history: Vec<Move>,
 }
 
-fn make_move(
-    command: &Direction,
-    robot: &mut (isize, isize),
-    boxes: &mut HashSet<(isize, isize)>,
-    walls: &HashSet<(isize, isize)>,
-    expand: bool,
-) {
-    let mut boxes_to_push = HashSet::new();
-
-    let mut frontier: HashSet<(isize, isize)> = HashSet::new();
-    match (command, expand) {
-        (Direction::Up, false) => {
-            frontier.insert((robot.0, robot.1 - 1));
-        }
-        (Direction::Up, true) => {
-            frontier.insert((robot.0, robot.1 - 1));
-            if !walls.contains(&(robot.0 - 1, robot.1 - 1)) {
-                frontier.insert((robot.0 - 1, robot.1 - 1));
+impl Warehouse {
+    fn parse(map: &str, wide: bool) -> Self {
+        let width = map.lines().next().unwrap().len() * if wide { 2 } else { 1 };
+        let mut tiles = vec![];
+        let mut robot = None;
+
+        for (y, line) in map.lines().enumerate() {
+            for c in line.chars() {
+                let tile = match c {
+                    '#' => Tile::Wall,
+                    'O' => Tile::Box,
+                    '.' => Tile::Empty,
+                    '@' => {
+                        robot = Some((tiles.len() % width, y));
+                        Tile::Empty
+                    }
+                    _ => panic!("Invalid character {c}"),
+                };
+
+                match (wide, tile) {
+                    (false, tile) => tiles.push(tile),
+                    (true, Tile::Box) => tiles.extend([Tile::BoxLeft, Tile::BoxRight]),
+                    (true, tile) => tiles.extend([tile, tile]),
+                }
             }
         }
-        (Direction::Down, false) => {
-            frontier.insert((robot.0, robot.1 + 1));
+
+        let count = |kind: Tile| tiles.iter().filter(|&&tile| tile == kind).count();
+        let walls = count(Tile::Wall);
+        let boxes = count(Tile::Box) + count(Tile::BoxLeft);
+
+        Warehouse {
+            tiles: Grid::new(width, tiles),
+            robot: robot.expect("No robot in the warehouse"),
+            walls,
+            boxes,
+            wall_tiles: walls,
+            box_tiles: boxes,
+            history: vec![],
         }
-        (Direction::Down, true) => {
-            frontier.insert((robot.0, robot.1 + 1));
-            if !walls.contains(&(robot.0 - 1, robot.1 + 1)) {
-                frontier.insert((robot.0 - 1, robot.1 + 1));
+    }
+
+    // The warehouse is surrounded by walls, so nothing can move off the edge
+    fn offset((x, y): (usize, usize), (dx, dy): (isize, isize)) -> (usize, usize) {
+        ((x as isize + dx) as usize, (y as isize + dy) as usize)
+    }
+
+    // Every tile that moves along with the robot, in the order they were reached, or
+    // None if anything in the chain is up against a wall
+    fn push_chain(&self, direction: Direction) -> Option<Vec<(usize, usize)>> {
+        let delta = direction.delta();
+        let vertical = delta.0 == 0;
+
+        let mut chain = vec![];
+        let mut frontier = vec![self.robot];
+        while let Some(position) = frontier.pop() {
+            let next = Self::offset(position, delta);
+            if chain.contains(&next) {
+                continue;
+            }
+
+            // Pushing either half of a wide box up or down moves the other half too
+            let other_half = match self.tiles[next] {
+                Tile::Wall => return None,
+                Tile::Empty => continue,
+                Tile::Box => None,
+                Tile::BoxLeft => vertical.then(|| Self::offset(next, (1, 0))),
+                Tile::BoxRight => vertical.then(|| Self::offset(next, (-1, 0))),
+            };
+
+            chain.push(next);
+            frontier.push(next);
+            if let Some(other_half) = other_half.filter(|half| !chain.contains(half)) {
+                chain.push(other_half);
+                frontier.push(other_half);
             }
         }
-        (Direction::Right, _) => {
-            frontier.insert((robot.0 + 1, robot.1));
+
+        Some(chain)
+    }
+
+    fn set(&mut self, position: (usize, usize), tile: Tile) {
+        let old = std::mem::replace(&mut self.tiles[position], tile);
+
+        // Wide boxes are counted by their left halves
+        let is_box = |tile| matches!(tile, Tile::Box | Tile::BoxLeft);
+        self.wall_tiles =
+            self.wall_tiles + (tile == Tile::Wall) as usize - (old == Tile::Wall) as usize;
+        self.box_tiles = self.box_tiles + is_box(tile) as usize - is_box(old) as usize;
+    }
+
+    fn shift(&mut self, positions: &[(usize, usize)], delta: (isize, isize)) {
+        let moved: Vec<Tile> = positions
+            .iter()
+            .map(|&position| self.tiles[position])
+            .collect();
+
+        for &position in positions {
+            self.set(position, Tile::Empty);
         }
-        (Direction::Left, false) => {
-            frontier.insert((robot.0 - 1, robot.1));
+        for (&position, tile) in positions.iter().zip(moved) {
+            self.set(Self::offset(position, delta), tile);
         }
-        (Direction::Left, true) => {
-            if boxes.contains(&(robot.0 - 2, robot.1)) {
-                frontier.insert((robot.0 - 2, robot.1));
-            } else {
-                frontier.insert((robot.0 - 1, robot.1));
+    }
+
+    // Moves the robot if nothing blocks it. Blocked moves still go in the history, so
+    // it always lines up with the list of moves
+    fn step(&mut self, direction: Direction) -> bool {
+        let delta = direction.delta();
+        let chain = self.push_chain(direction);
+
+        let pushed = match &chain {
+            Some(chain) => {
+                self.shift(chain, delta);
+                chain
+                    .iter()
+                    .map(|&position| Self::offset(position, delta))
+                    .collect()
             }
+            None => vec![],
+        };
+
+        self.history.push(Move {
+            direction,
+            robot: self.robot,
+            pushed,
+        });
+        if chain.is_some() {
+            self.robot = Self::offset(self.robot, delta);
         }
-    };
 
-    loop {
-        let current = match frontier.iter().next() {
-            Some(current) => *current,
-            None => break,
-        };
-        frontier.remove(&current);
+        chain.is_some()
+    }
+
+    fn undo(&mut self) -> Option<Direction> {
+        let last = self.history.pop()?;
+        let (dx, dy) = last.direction.delta();
 
-        let is_wall = walls.contains(&current);
-        let is_box = boxes.contains(&current);
+        self.shift(&last.pushed, (-dx, -dy));
+        self.robot = last.robot;
 
-        if !(is_wall || is_box) {
-            continue;
+        Some(last.direction)
+    }
+
+    // Errors come with the number of the move that caused them
+    fn replay(&mut self, moves: &[Direction]) -> Result<(), (usize, WarehouseError)> {
+        for (i, &direction) in moves.iter().enumerate() {
+            self.step(direction);
+            self.validate_last_move().map_err(|error| (i + 1, error))?;
         }
 
-        if is_wall {
-            boxes_to_push.clear();
-            return;
+        Ok(())
+    }
+
+    fn check_robot(&self) -> Result<(), WarehouseError> {
+        if self.tiles[self.robot] != Tile::Empty {
+            return Err(WarehouseError::RobotInside(self.robot));
         }
 
-        if !is_box {
-            continue;
+        Ok(())
+    }
+
+    fn check_split(&self, position @ (x, y): (usize, usize)) -> Result<(), WarehouseError> {
+        let (x, y) = (x as isize, y as isize);
+        let split = match self.tiles.get(x, y) {
+            Some(Tile::BoxLeft) => self.tiles.get(x + 1, y) != Some(&Tile::BoxRight),
+            Some(Tile::BoxRight) => self.tiles.get(x - 1, y) != Some(&Tile::BoxLeft),
+            _ => false,
+        };
+
+        if split {
+            return Err(WarehouseError::SplitBox(position));
         }
-        boxes_to_push.insert(current);
 
-        match (command, expand) {
-            (Direction::Right, false) => {
-                frontier.insert((current.0 + 1, current.1));
-            }
-            (Direction::Right, true) => {
-                // Must be on a box, so next non-box tile is 2 away
-                frontier.insert((current.0 + 2, current.1));
-            }
-            (Direction::Left, false) => {
-                frontier.insert((current.0 - 1, current.1));
-            }
-            (Direction::Left, true) => {
-                if boxes.contains(&(current.0 - 2, current.1)) {
-                    frontier.insert((current.0 - 2, current.1));
-                } else {
-                    frontier.insert((current.0 - 1, current.1));
-                }
-            }
-            (Direction::Up, false) => {
-                frontier.insert((current.0, current.1 - 1));
-            }
-            (Direction::Up, true) => {
-                frontier.insert((current.0, current.1 - 1));
-                frontier.insert((current.0 + 1, current.1 - 1));
-                if boxes.contains(&(current.0 - 1, current.1 - 1)) {
-                    frontier.insert((current.0 - 1, current.1 - 1));
+        Ok(())
+    }
+
+    // Only looks at the cells the last move touched, and the running totals
+    fn validate_last_move(&self) -> Result<(), WarehouseError> {
+        self.check_robot()?;
+
+        // A box split by the move has a half either among the pushed cells or beside them
+        if let Some(last) = self.history.last() {
+            for &(x, y) in &last.pushed {
+                for x in [x.checked_sub(1), Some(x), x.checked_add(1)]
+                    .into_iter()
+                    .flatten()
+                {
+                    self.check_split((x, y))?;
                 }
             }
-            (Direction::Down, false) => {
-                frontier.insert((current.0, current.1 + 1));
-            }
-            (Direction::Down, true) => {
-                frontier.insert((current.0, current.1 + 1));
-                frontier.insert((current.0 + 1, current.1 + 1));
-                if boxes.contains(&(current.0 - 1, current.1 + 1)) {
-                    frontier.insert((current.0 - 1, current.1 + 1));
-                }
+        }
+
+        Self::check_totals((self.wall_tiles, self.box_tiles), (self.walls, self.boxes))
+    }
+
+    fn check_totals(
+        (walls, boxes): (usize, usize),
+        (expected_walls, expected_boxes): (usize, usize),
+    ) -> Result<(), WarehouseError> {
+        match (walls == expected_walls, boxes == expected_boxes) {
+            (false, _) => Err(WarehouseError::BoxInWall),
+            (_, false) => Err(WarehouseError::LostBox),
+            _ => Ok(()),
+        }
+    }
+
+    // Checks every cell and counts the tiles again, so it also catches tiles changed
+    // without going through `set`
+    fn validate(&self) -> Result<(), WarehouseError> {
+        self.check_robot()?;
+
+        let (mut walls, mut boxes) = (0, 0);
+        for position in self.tiles.positions() {
+            self.check_split(position)?;
+
+            match self.tiles[position] {
+                Tile::Wall => walls += 1,
+                Tile::Box | Tile::BoxLeft => boxes += 1,
+                _ => {}
             }
         }
+
+        Self::check_totals((walls, boxes), (self.walls, self.boxes))
     }
 
-    let push_delta = match command {
-        Direction::Up => (0, -1),
-        Direction::Down => (0, 1),
-        Direction::Left => (-1, 0),
-        Direction::Right => (1, 0),
-    };
-
-    if !boxes_to_push.is_empty()
-        || !walls.contains(&(robot.0 + push_delta.0, robot.1 + push_delta.1))
-    {
-        *robot = (robot.0 + push_delta.0, robot.1 + push_delta.1);
+    fn gps_sum(&self) -> usize {
+        self.tiles
+            .positions()
+            .filter(|&position| matches!(self.tiles[position], Tile::Box | Tile::BoxLeft))
+            .map(|(x, y)| x + 100 * y)
+            .sum()
     }
+}
 
-    if boxes_to_push.is_empty() {
-        return;
+// Draws the warehouse the same way as the puzzle input
+impl Display for Warehouse {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for y in 0..self.tiles.height() {
+            let row: String = (0..self.tiles.width())
+                .map(|x| match self.tiles[(x, y)] {
+                    _ if (x, y) == self.robot => '@',
+                    Tile::Empty => '.',
+                    Tile::Wall => '#',
+                    Tile::Box => 'O',
+                    Tile::BoxLeft => '[',
+                    Tile::BoxRight => ']',
+                })
+                .collect();
+            writeln!(f, "{row}")?;
+        }
+
+        Ok(())
     }
+}
+
+fn parse_file(file: &str, wide: bool) -> (Warehouse, Vec<Direction>) {
+    let contents = fs::read_to_string(file).unwrap();
+    let (map, moves) = contents.split_once("\n\n").unwrap();
 
-    let new_boxes: HashSet<(isize, isize)> = boxes_to_push
-        .iter()
-        .map(|r#box| (r#box.0 + push_delta.0, r#box.1 + push_delta.1))
+    let moves = moves
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| match c {
+            '^' => Direction::Up,
+            'v' => Direction::Down,
+            '<' => Direction::Left,
+            '>' => Direction::Right,
+            _ => panic!("Invalid direction {c}"),
+        })
         .collect();
 
-    let boxes_to_remove = boxes_to_push.difference(&new_boxes);
-    let boxes_to_add = new_boxes.difference(&boxes_to_push);
+    (Warehouse::parse(map, wide), moves)
+}
+
+fn sum_pushed_coordinates(file: &str, wide: bool) -> u128 {
+    let (mut warehouse, moves) = parse_file(file, wide);
 
-    boxes_to_remove.for_each(|r#box| {
-        boxes.remove(r#box);
-    });
+    warehouse
+        .replay(&moves)
+        .unwrap_or_else(|(i, error)| panic!("After move {i}, {error}"));
+
+    warehouse.gps_sum() as u128
+}
 
-    boxes_to_add.for_each(|r#box| {
-        boxes.insert(*r#box);
-    });
+pub fn debug_warehouse(file: &str, wide: bool, input: impl BufRead, mut output: impl Write) {
+    // Steps back and forth through the moves, reading one command per line from input
+    let (mut warehouse, moves) = parse_file(file, wide);
+
+    let help = "Commands: s|step [n], b|back [n], c|continue, p|print, q|quit";
+    writeln!(output, "{warehouse}{help}").unwrap();
+
+    let mut lines = input.lines();
+    loop {
+        write!(
+            output,
+            "(move {}/{}) > ",
+            warehouse.history.len(),
+            moves.len()
+        )
+        .unwrap();
+        output.flush().unwrap();
+
+        let Some(Ok(line)) = lines.next() else {
+            break;
+        };
+        let command: Vec<&str> = line.split_whitespace().collect();
+        let count = command.get(1).and_then(|n| n.parse().ok()).unwrap_or(1);
+
+        match command.as_slice() {
+            ["s" | "step"] | ["s" | "step", _] | ["c" | "continue"] => {
+                let count = if command[0].starts_with('c') {
+                    moves.len()
+                } else {
+                    count
+                };
+
+                for _ in 0..count {
+                    let Some(&direction) = moves.get(warehouse.history.len()) else {
+                        writeln!(output, "No moves left").unwrap();
+                        break;
+                    };
+
+                    let moved = warehouse.step(direction);
+                    writeln!(
+                        output,
+                        "Move {direction}{}:",
+                        if moved { "" } else { " (blocked)" }
+                    )
+                    .unwrap();
+
+                    if let Err(error) = warehouse.validate() {
+                        writeln!(output, "Error: {error}").unwrap();
+                        break;
+                    }
+                }
+                writeln!(output, "{warehouse}").unwrap();
+            }
+            ["b" | "back"] | ["b" | "back", _] => {
+                for _ in 0..count {
+                    match warehouse.undo() {
+                        Some(direction) => writeln!(output, "Undid {direction}").unwrap(),
+                        None => {
+                            writeln!(output, "Already at the start").unwrap();
+                            break;
+                        }
+                    }
+                }
+                writeln!(output, "{warehouse}").unwrap();
+            }
+            ["p" | "print"] => {
+                writeln!(output, "{warehouse}GPS sum: {}", warehouse.gps_sum()).unwrap()
+            }
+            ["q" | "quit"] => break,
+            _ => writeln!(output, "{help}").unwrap(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -269,4 +495,109 @@ mod tests {
             509
         );
     }
+
+    #[test]
+    fn test_render() {
+        let (mut warehouse, moves) = parse_file("resources/2024/day15/test_input_2.txt", false);
+        warehouse.replay(&moves).unwrap();
+
+        assert_eq!(
+            warehouse.to_string(),
+            "########
+#....OO#
+##.....#
+#.....O#
+#.#O@..#
+#...O..#
+#...O..#
+########
+"
+        );
+
+        let (mut warehouse, moves) = parse_file("resources/2024/day15/test_input.txt", true);
+        warehouse.replay(&moves).unwrap();
+
+        assert_eq!(
+            warehouse.to_string(),
+            "####################
+##[].......[].[][]##
+##[]...........[].##
+##[]........[][][]##
+##[]......[]....[]##
+##..##......[]....##
+##..[]............##
+##..@......[].[][]##
+##......[][]..[]..##
+####################
+"
+        );
+    }
+
+    #[test]
+    fn test_undo() {
+        let (mut warehouse, moves) = parse_file("resources/2024/day15/test_input.txt", true);
+        let start = warehouse.to_string();
+
+        warehouse.replay(&moves).unwrap();
+        let end = warehouse.to_string();
+
+        let recorded: Vec<Direction> = warehouse.history.iter().map(|m| m.direction).collect();
+        assert_eq!(recorded, moves);
+
+        while warehouse.undo().is_some() {
+            warehouse.validate().unwrap();
+        }
+        assert_eq!(warehouse.to_string(), start);
+
+        warehouse.replay(&recorded).unwrap();
+        assert_eq!(warehouse.to_string(), end);
+    }
+
+    #[test]
+    fn test_validate() {
+        let (mut warehouse, _) = parse_file("resources/2024/day15/test_input_5.txt", true);
+        assert_eq!(warehouse.validate(), Ok(()));
+
+        warehouse.tiles[(5, 2)] = Tile::Empty;
+        assert_eq!(warehouse.validate(), Err(WarehouseError::SplitBox((4, 2))));
+
+        warehouse.tiles[(4, 2)] = Tile::Empty;
+        assert_eq!(warehouse.validate(), Err(WarehouseError::LostBox));
+
+        warehouse.tiles[(4, 2)] = Tile::Wall;
+        warehouse.tiles[(5, 2)] = Tile::Wall;
+        assert_eq!(warehouse.validate(), Err(WarehouseError::BoxInWall));
+    }
+
+    #[test]
+    fn test_validate_last_move() {
+        let (mut warehouse, moves) = parse_file("resources/2024/day15/test_input_5.txt", true);
+        warehouse.step(moves[0]);
+        assert_eq!(warehouse.validate_last_move(), Ok(()));
+
+        // A box ending up in the wall on the left edge is caught by the move that did it
+        warehouse.set((0, 2), Tile::Box);
+        warehouse.history.last_mut().unwrap().pushed.push((0, 2));
+        assert_eq!(
+            warehouse.validate_last_move(),
+            Err(WarehouseError::BoxInWall)
+        );
+    }
+
+    #[test]
+    fn test_debug_warehouse() {
+        let mut output = vec![];
+        debug_warehouse(
+            "resources/2024/day15/test_input_5.txt",
+            true,
+            "s\nb 2\nc\nq\n".as_bytes(),
+            &mut output,
+        );
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.contains("Move <:\n##############\n##..........##\n##.[][]@....##\n"));
+        assert!(output.contains("Undid <\nAlready at the start\n"));
+        assert!(output.contains("##[][]@.....##\n"));
+        assert!(output.ends_with("(move 2/2) > "));
+    }
 }
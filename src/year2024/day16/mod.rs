@@ -1,246 +1,353 @@
-use std::{
-    collections::{HashMap, HashSet},
-    fs,
-};
+use std::{cmp::Reverse, collections::BinaryHeap, io::Write, str::FromStr};
+
+use crate::grid::Grid;
 
 pub mod task1 {
-    use super::lowest_scoring_path;
+    use super::{lowest_scoring_path, PUZZLE_COSTS};
 
     pub fn ans() -> u128 {
-        lowest_scoring_path("resources/2024/day16/input.txt")
+        lowest_scoring_path("resources/2024/day16/input.txt", PUZZLE_COSTS)
     }
 }
 
 pub mod task2 {
-    use super::tiles_on_shortest_paths;
+    use super::{tiles_on_shortest_paths, PUZZLE_COSTS};
 
     pub fn ans() -> u128 {
-        tiles_on_shortest_paths("resources/2024/day16/input.txt")
+        tiles_on_shortest_paths("resources/2024/day16/input.txt", PUZZLE_COSTS)
     }
 }
 
-#[derive(Clone, Copy, Eq, PartialEq, Hash)]
-enum Direction {
-    Up,
-    Down,
-    Left,
-    Right,
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Costs {
+    step: u64,
+    turn: u64,
 }
 
-fn lowest_scoring_path(file: &str) -> u128 {
-    let content = fs::read_to_string(file).unwrap();
-
-    let map: Vec<Vec<char>> = content.lines().map(|line| line.chars().collect()).collect();
+pub const PUZZLE_COSTS: Costs = Costs {
+    step: 1,
+    turn: 1000,
+};
 
-    let mut start = (0, 0);
-    let mut end = (0, 0);
+impl FromStr for Costs {
+    type Err = String;
+
+    // Free moves would let the reindeer spin on the spot forever, so both must cost something
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (step, turn) = s
+            .split_once(',')
+            .ok_or_else(|| format!("Costs {s} should look like 1,1000"))?;
+        let cost = |cost: &str| match cost.parse() {
+            Ok(cost) if cost > 0 => Ok(cost),
+            _ => Err(format!("Invalid cost {cost} in {s}")),
+        };
 
-    map.iter()
-        .enumerate()
-        .flat_map(|(y, row)| row.into_iter().enumerate().map(move |(x, c)| (x, y, c)))
-        .for_each(|(x, y, c)| {
-            if c == &'S' {
-                start = (x as isize, y as isize);
-            } else if c == &'E' {
-                end = (x as isize, y as isize);
-            }
-        });
-
-    solve_scores(map, start, end)
-        .into_iter()
-        .filter(|((pos, _), _)| pos == &end)
-        .map(|(_, (score, _))| score as u128)
-        .min()
-        .unwrap()
+        Ok(Costs {
+            step: cost(step)?,
+            turn: cost(turn)?,
+        })
+    }
 }
 
-fn tiles_on_shortest_paths(file: &str) -> u128 {
-    // Solve the maze, then backtrack through the graph of shortest paths,
-    // collecting the tiles that are part of the shortest paths and count
+// Facing up, right, down and left, so turning clockwise adds one
+const DIRECTIONS: [(isize, isize); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+const ARROWS: [char; 4] = ['^', '>', 'v', '<'];
+const EAST: usize = 1;
+
+// Quarter turns between two directions, so turning around takes two
+fn turns(from: usize, to: usize) -> u64 {
+    match (to + 4 - from) % 4 {
+        0 => 0,
+        2 => 2,
+        _ => 1,
+    }
+}
 
-    let content = fs::read_to_string(file).unwrap();
+const UNREACHABLE: u64 = u64::MAX;
 
-    let map: Vec<Vec<char>> = content.lines().map(|line| line.chars().collect()).collect();
+// A state is a tile and the direction the reindeer faced as it arrived there, packed as
+// tile * 4 + direction. Each move turns if needed and then steps one tile forward
+struct Maze {
+    tiles: Grid<char>,
+    start: (usize, usize),
+    end: (usize, usize),
+}
 
-    let mut start = (0, 0);
-    let mut end = (0, 0);
+// Lowest scores from the start and to the end for every state, with the number of
+// lowest scoring paths from the start into each one
+struct Scores {
+    from_start: Vec<u64>,
+    to_end: Vec<u64>,
+    paths: Vec<u128>,
+    best: u64,
+}
 
-    map.iter()
-        .enumerate()
-        .flat_map(|(y, row)| row.into_iter().enumerate().map(move |(x, c)| (x, y, c)))
-        .for_each(|(x, y, c)| {
-            if c == &'S' {
-                start = (x as isize, y as isize);
-            } else if c == &'E' {
-                end = (x as isize, y as isize);
-            }
-        });
+// A route through the maze, as the states it passes through
+struct Route {
+    score: u64,
+    states: Vec<usize>,
+}
 
-    let scores = solve_scores(map, start, end);
+impl Maze {
+    fn from_file(file: &str) -> Self {
+        let tiles = Grid::from_file(file);
+        let find = |target| {
+            tiles
+                .positions()
+                .find(|&position| tiles[position] == target)
+                .unwrap_or_else(|| panic!("No {target} in the maze"))
+        };
+        let (start, end) = (find('S'), find('E'));
 
-    let shortest_path = scores
-        .iter()
-        .filter(|((pos, _), _)| pos == &end)
-        .map(|(_, (score, _))| score)
-        .min()
-        .unwrap();
+        Maze { tiles, start, end }
+    }
 
-    let end_backtracks = scores
-        .iter()
-        .filter(|((pos, _), _)| pos == &end)
-        .filter(|(_, (score, _))| score == shortest_path)
-        .map(|(_, (_, backtrack))| backtrack)
-        .flatten()
-        .collect::<HashSet<_>>();
-
-    // Pairs of tiles and backtracks which lead to them
-    let mut frontier = end_backtracks
-        .into_iter()
-        .map(|pos| (end, pos))
-        .collect::<HashSet<_>>();
-
-    let mut tiles = HashSet::new();
-    tiles.insert(end);
-
-    loop {
-        let current = match frontier.iter().next() {
-            Some(pos) => *pos,
-            None => break,
-        };
-        frontier.remove(&current);
+    fn state(&self, (x, y): (usize, usize), direction: usize) -> usize {
+        (y * self.tiles.width() + x) * 4 + direction
+    }
 
-        let (end_pos, (prev_pos, direction)) = current;
+    fn position(&self, state: usize) -> (usize, usize) {
+        let tile = state / 4;
+        (tile % self.tiles.width(), tile / self.tiles.width())
+    }
 
-        let step = match direction {
-            Direction::Up => (0, -1),
-            Direction::Down => (0, 1),
-            Direction::Left => (-1, 0),
-            Direction::Right => (1, 0),
-        };
+    fn open(&self, (x, y): (usize, usize), (dx, dy): (isize, isize)) -> Option<(usize, usize)> {
+        let (x, y) = (x as isize + dx, y as isize + dy);
+        match self.tiles.get(x, y) {
+            Some('#') | None => None,
+            _ => Some((x as usize, y as usize)),
+        }
+    }
 
-        let (mut x, mut y) = prev_pos;
-        while (x, y) != end_pos {
-            tiles.insert((x, y));
-            x += step.0;
-            y += step.1;
+    // Routes stop as soon as they reach the end
+    fn moves_from(&self, state: usize, costs: Costs) -> Vec<(usize, u64)> {
+        let position = self.position(state);
+        if position == self.end {
+            return vec![];
         }
 
-        tiles.insert(end_pos);
+        (0..4)
+            .filter_map(|direction| {
+                let next = self.open(position, DIRECTIONS[direction])?;
+                let cost = costs.step + costs.turn * turns(state % 4, direction);
+                Some((self.state(next, direction), cost))
+            })
+            .collect()
+    }
+
+    fn moves_into(&self, state: usize, costs: Costs) -> Vec<(usize, u64)> {
+        let (dx, dy) = DIRECTIONS[state % 4];
+        let Some(previous) = self.open(self.position(state), (-dx, -dy)) else {
+            return vec![];
+        };
+        if previous == self.end {
+            return vec![];
+        }
 
-        let (_, previous) = scores.get(&(*prev_pos, *direction)).unwrap();
-        previous
-            .into_iter()
-            .map(|pos| (*prev_pos, pos))
-            .for_each(|pos| {
-                frontier.insert(pos);
-            });
+        (0..4)
+            .map(|direction| {
+                let cost = costs.step + costs.turn * turns(direction, state % 4);
+                (self.state(previous, direction), cost)
+            })
+            .collect()
     }
 
-    tiles.len() as u128
-}
+    // Dijkstra's algorithm, counting the lowest scoring paths into each state on the way.
+    // Every move costs something, so a state's count is final by the time it's popped
+    fn search(
+        &self,
+        sources: &[usize],
+        moves: impl Fn(usize) -> Vec<(usize, u64)>,
+    ) -> (Vec<u64>, Vec<u128>) {
+        let states = self.tiles.width() * self.tiles.height() * 4;
+        let mut scores = vec![UNREACHABLE; states];
+        let mut paths = vec![0; states];
+
+        let mut queue = BinaryHeap::new();
+        for &source in sources {
+            scores[source] = 0;
+            paths[source] = 1;
+            queue.push(Reverse((0, source)));
+        }
 
-fn solve_scores(
-    map: Vec<Vec<char>>,
-    start: (isize, isize),
-    end: (isize, isize),
-) -> HashMap<((isize, isize), Direction), (i32, HashSet<((isize, isize), Direction)>)> {
-    let mut frontier = vec![(start, Direction::Right)];
-
-    // Stores position, direction, and the previous turns that reach the position with the lowest score
-    let mut scores: HashMap<
-        ((isize, isize), Direction),
-        (i32, HashSet<((isize, isize), Direction)>),
-    > = HashMap::new();
-    scores.insert((start, Direction::Right), (0, HashSet::new()));
-
-    while let Some(current) = frontier.pop() {
-        let (pos, direction) = current;
-        let (mut score, _) = *scores.get(&(pos, direction)).unwrap();
-
-        let rotations = adjacent_rotations(&direction);
-        for new_dir in rotations.into_iter() {
-            let rotation = (pos, new_dir);
-            if let Some((prev_score, prev_backtrack)) = scores.get_mut(&rotation) {
-                if &(score + 1000) < prev_score {
-                    *prev_score = score + 1000;
-                    *prev_backtrack = HashSet::from([current]);
-                    frontier.push(rotation);
-                } else if &(score + 1000) == prev_score {
-                    prev_backtrack.insert(current);
+        while let Some(Reverse((score, state))) = queue.pop() {
+            if score > scores[state] {
+                continue;
+            }
+
+            for (next, cost) in moves(state) {
+                let next_score = score + cost;
+                if next_score < scores[next] {
+                    scores[next] = next_score;
+                    paths[next] = paths[state];
+                    queue.push(Reverse((next_score, next)));
+                } else if next_score == scores[next] {
+                    paths[next] += paths[state];
                 }
-            } else {
-                scores.insert(rotation, (score + 1000, HashSet::from([current])));
-                frontier.push(rotation);
             }
         }
 
-        let (mut x, mut y) = pos;
+        (scores, paths)
+    }
 
-        let step = match direction {
-            Direction::Up => (0, -1),
-            Direction::Down => (0, 1),
-            Direction::Left => (-1, 0),
-            Direction::Right => (1, 0),
-        };
+    fn scores(&self, costs: Costs) -> Scores {
+        let start = self.state(self.start, EAST);
+        let ends: Vec<usize> = (0..4)
+            .map(|direction| self.state(self.end, direction))
+            .collect();
 
-        let neighbours = |(x, y): (isize, isize)| match direction {
-            Direction::Up | Direction::Down => [
-                ((x - 1, y), Direction::Left),
-                ((x + 1, y), Direction::Right),
-            ],
-            Direction::Left | Direction::Right => {
-                [((x, y - 1), Direction::Up), ((x, y + 1), Direction::Down)]
-            }
-        };
+        let (from_start, paths) = self.search(&[start], |state| self.moves_from(state, costs));
+        let (to_end, _) = self.search(&ends, |state| self.moves_into(state, costs));
+
+        Scores {
+            best: to_end[start],
+            from_start,
+            to_end,
+            paths,
+        }
+    }
 
-        loop {
-            x += step.0;
-            y += step.1;
-            let new_pos = (x, y);
-            score += 1;
+    // Best first search over partial routes, ranked by their score so far plus the lowest
+    // score left to the end. Routes that double back onto their own tiles are dropped, so
+    // complete routes come out cheapest first
+    fn best_routes(&self, scores: &Scores, costs: Costs, count: usize) -> Vec<Route> {
+        let start = self.state(self.start, EAST);
 
-            if &map[y as usize][x as usize] == &'#' {
+        // Partial routes share their beginnings, each state pointing back to the one before
+        let mut steps = vec![(start, usize::MAX)];
+        let mut queue = BinaryHeap::from([Reverse((scores.to_end[start], 0, 0))]);
+        let mut routes = vec![];
+
+        while let Some(Reverse((_, score, step))) = queue.pop() {
+            if routes.len() == count {
                 break;
             }
 
-            let neighbours = neighbours(new_pos);
-            let valid_neighbours = neighbours
-                .into_iter()
-                .filter(|((x, y), _)| map[*y as usize][*x as usize] != '#')
-                .collect::<Vec<_>>();
+            let mut states = vec![];
+            let mut previous = step;
+            while previous != usize::MAX {
+                states.push(steps[previous].0);
+                previous = steps[previous].1;
+            }
+            states.reverse();
 
-            if !(!valid_neighbours.is_empty() || new_pos == end) {
+            let state = steps[step].0;
+            if self.position(state) == self.end {
+                routes.push(Route { score, states });
                 continue;
             }
 
-            // Add straight path
-            if let Some((prev_score, prev_backtrack)) = scores.get_mut(&(new_pos, direction)) {
-                // If score is lower, replace it
-                if &score < prev_score {
-                    *prev_score = score;
-                    *prev_backtrack = HashSet::from([current]);
-                    frontier.push((new_pos, direction));
-                } else if &score == prev_score {
-                    // If score matches, add last turn to the set
-                    prev_backtrack.insert(current);
+            for (next, cost) in self.moves_from(state, costs) {
+                let revisits = states
+                    .iter()
+                    .any(|&state| self.position(state) == self.position(next));
+                if revisits || scores.to_end[next] == UNREACHABLE {
+                    continue;
                 }
-                // If score is higher, ignore it
-            } else {
-                scores.insert((new_pos, direction), (score, HashSet::from([current])));
-                frontier.push((new_pos, direction));
+
+                steps.push((next, step));
+                queue.push(Reverse((
+                    score + cost + scores.to_end[next],
+                    score + cost,
+                    steps.len() - 1,
+                )));
             }
+        }
+
+        routes
+    }
 
-            break;
+    // Draws the route with the direction the reindeer faces on each tile it passes
+    fn draw(&self, route: &Route) -> String {
+        let mut tiles = self.tiles.clone();
+        for &state in &route.states {
+            let position = self.position(state);
+            if position != self.start && position != self.end {
+                tiles[position] = ARROWS[state % 4];
+            }
         }
+
+        (0..tiles.height())
+            .map(|y| {
+                (0..tiles.width())
+                    .map(|x| tiles[(x, y)])
+                    .collect::<String>()
+                    + "\n"
+            })
+            .collect()
     }
+}
+
+impl Scores {
+    fn on_best_path(&self, state: usize) -> bool {
+        self.from_start[state] != UNREACHABLE
+            && self.to_end[state] != UNREACHABLE
+            && self.from_start[state] + self.to_end[state] == self.best
+    }
+}
+
+impl Maze {
+    fn tiles_on_best_paths(&self, scores: &Scores) -> usize {
+        self.tiles
+            .positions()
+            .filter(|&position| {
+                (0..4).any(|direction| scores.on_best_path(self.state(position, direction)))
+            })
+            .count()
+    }
+
+    fn count_best_paths(&self, scores: &Scores) -> u128 {
+        (0..4)
+            .map(|direction| self.state(self.end, direction))
+            .filter(|&state| scores.from_start[state] == scores.best)
+            .map(|state| scores.paths[state])
+            .sum()
+    }
+}
+
+fn lowest_scoring_path(file: &str, costs: Costs) -> u128 {
+    let maze = Maze::from_file(file);
+
+    maze.scores(costs).best as u128
+}
+
+fn tiles_on_shortest_paths(file: &str, costs: Costs) -> u128 {
+    let maze = Maze::from_file(file);
 
-    scores
+    maze.tiles_on_best_paths(&maze.scores(costs)) as u128
 }
 
-fn adjacent_rotations(direction: &Direction) -> [Direction; 2] {
-    match direction {
-        Direction::Up | Direction::Down => [Direction::Left, Direction::Right],
-        Direction::Left | Direction::Right => [Direction::Down, Direction::Up],
+// Summarises the lowest scoring paths, draws one of them, and lists the best few routes
+pub fn explore_routes(file: &str, costs: Costs, count: usize, mut output: impl Write) {
+    let maze = Maze::from_file(file);
+    let scores = maze.scores(costs);
+    if scores.best == UNREACHABLE {
+        writeln!(output, "The end can't be reached").unwrap();
+        return;
+    }
+
+    writeln!(
+        output,
+        "Lowest score {} along {} paths over {} tiles",
+        scores.best,
+        maze.count_best_paths(&scores),
+        maze.tiles_on_best_paths(&scores)
+    )
+    .unwrap();
+
+    let routes = maze.best_routes(&scores, costs, count.max(1));
+    write!(output, "{}", maze.draw(&routes[0])).unwrap();
+
+    for (i, route) in routes.iter().take(count).enumerate() {
+        let steps = route.states.len() as u64 - 1;
+        let turns = (route.score - steps * costs.step) / costs.turn;
+        writeln!(
+            output,
+            "{}. score {} from {steps} steps and {turns} turns",
+            i + 1,
+            route.score
+        )
+        .unwrap();
     }
 }
 
@@ -251,12 +358,12 @@ mod tests {
     #[test]
     fn test_lowest_scoring_path() {
         assert_eq!(
-            lowest_scoring_path("resources/2024/day16/test_input_2.txt"),
+            lowest_scoring_path("resources/2024/day16/test_input_2.txt", PUZZLE_COSTS),
             7036
         );
 
         assert_eq!(
-            lowest_scoring_path("resources/2024/day16/test_input.txt"),
+            lowest_scoring_path("resources/2024/day16/test_input.txt", PUZZLE_COSTS),
             11048
         )
     }
@@ -264,13 +371,58 @@ mod tests {
     #[test]
     fn test_tiles_on_shortest_paths() {
         assert_eq!(
-            tiles_on_shortest_paths("resources/2024/day16/test_input_2.txt"),
+            tiles_on_shortest_paths("resources/2024/day16/test_input_2.txt", PUZZLE_COSTS),
             45
         );
 
         assert_eq!(
-            tiles_on_shortest_paths("resources/2024/day16/test_input.txt"),
+            tiles_on_shortest_paths("resources/2024/day16/test_input.txt", PUZZLE_COSTS),
             64
         )
     }
+
+    #[test]
+    fn test_costs() {
+        let maze = Maze::from_file("resources/2024/day16/test_input.txt");
+        assert_eq!(maze.count_best_paths(&maze.scores(PUZZLE_COSTS)), 2);
+
+        let maze = Maze::from_file("resources/2024/day16/test_input_2.txt");
+        assert_eq!(maze.count_best_paths(&maze.scores(PUZZLE_COSTS)), 3);
+
+        // With turns as cheap as steps, a route with fewer steps but more turns wins
+        let scores = maze.scores("1,1".parse().unwrap());
+        assert_eq!(scores.best, 38);
+        assert_eq!(maze.count_best_paths(&scores), 3);
+        assert_eq!(maze.tiles_on_best_paths(&scores), 37);
+
+        assert!("1,0".parse::<Costs>().is_err());
+        assert!("1000".parse::<Costs>().is_err());
+    }
+
+    #[test]
+    fn test_explore_routes() {
+        let mut output = vec![];
+        explore_routes(
+            "resources/2024/day16/test_input_2.txt",
+            PUZZLE_COSTS,
+            4,
+            &mut output,
+        );
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines[0], "Lowest score 7036 along 3 paths over 45 tiles");
+        assert_eq!(lines[2], "#.......#....E#");
+        assert_eq!(lines[8], "#....^>>>>>>#^#");
+        assert_eq!(lines[14], "#S..#.....#v>>#");
+        assert_eq!(
+            lines[16..],
+            [
+                "1. score 7036 from 36 steps and 7 turns",
+                "2. score 7036 from 36 steps and 7 turns",
+                "3. score 7036 from 36 steps and 7 turns",
+                "4. score 9040 from 40 steps and 9 turns",
+            ]
+        );
+    }
 }
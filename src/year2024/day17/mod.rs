@@ -1,4 +1,8 @@
-use std::fs;
+use std::{
+    collections::HashSet,
+    fmt, fs,
+    io::{BufRead, Write},
+};
 
 pub mod task1 {
     use super::concat_output;
@@ -9,11 +13,14 @@ pub mod task1 {
 }
 
 pub mod task2 {
+    use super::lowest_quine_register;
+
     pub fn ans() -> u128 {
-        0
+        lowest_quine_register("resources/2024/day17/input.txt")
     }
 }
 
+#[derive(Clone)]
 struct Program {
     ip: isize,
     program: Vec<u128>,
@@ -56,19 +63,23 @@ impl Program {
         }
     }
 
-    fn next_op(&mut self) -> Option<()> {
+    fn next_op(&mut self) -> Result<Option<()>, VmError> {
         if self.ip < 0 || self.ip >= self.program.len() as isize {
-            return None;
+            return Ok(None);
         }
 
-        let opcode = self.program[self.ip as usize];
-        let literal_operand = self.program[(self.ip + 1) as usize];
+        let ip = self.ip as usize;
+        let opcode = self.program[ip];
+        let literal_operand = *self
+            .program
+            .get(ip + 1)
+            .ok_or(VmError::MissingOperand { ip })?;
         let combo_operand = match literal_operand {
-            4 => Some(self.reg_a),
-            5 => Some(self.reg_b),
-            6 => Some(self.reg_c),
-            7 => None,
-            literal => Some(literal),
+            4 => Ok(self.reg_a),
+            5 => Ok(self.reg_b),
+            6 => Ok(self.reg_c),
+            7 => Err(VmError::InvalidComboOperand { ip }),
+            literal => Ok(literal),
         };
 
         match opcode {
@@ -77,7 +88,7 @@ impl Program {
                 let numerator = self.reg_a;
 
                 // 2^operand
-                let denominator = (1 << combo_operand.unwrap()) as u128;
+                let denominator = (1 << combo_operand?) as u128;
 
                 self.reg_a = numerator / denominator;
             }
@@ -87,7 +98,7 @@ impl Program {
             }
             2 => {
                 // modulo
-                let numerator = combo_operand.unwrap();
+                let numerator = combo_operand?;
                 let denominator = 8;
 
                 self.reg_b = numerator % denominator;
@@ -105,14 +116,14 @@ impl Program {
             }
             5 => {
                 // OUT
-                self.output_buffer.push(combo_operand.unwrap() % 8);
+                self.output_buffer.push(combo_operand? % 8);
             }
             6 => {
                 // b division
                 let numerator = self.reg_a;
 
                 // 2^operand
-                let denominator = (1 << combo_operand.unwrap()) as u128;
+                let denominator = (1 << combo_operand?) as u128;
 
                 self.reg_b = numerator / denominator;
             }
@@ -121,23 +132,149 @@ impl Program {
                 let numerator = self.reg_a;
 
                 // 2^operand
-                let denominator = (1 << combo_operand.unwrap()) as u128;
+                let denominator = (1 << combo_operand?) as u128;
 
                 self.reg_c = numerator / denominator;
             }
-            _ => panic!("Invalid opcode"),
+            opcode => return Err(VmError::InvalidOpcode { ip, opcode }),
         }
 
         self.ip += 2;
 
-        Some(())
+        Ok(Some(()))
+    }
+
+    fn run(&mut self) -> Result<(), VmError> {
+        while self.next_op()?.is_some() {}
+
+        Ok(())
+    }
+
+    // Runs one instruction, recording what it was and the registers after it ran
+    fn traced_op(&mut self) -> Result<Option<TraceStep>, VmError> {
+        let ip = self.ip;
+        let instruction = self.instruction_at(ip as usize);
+        let output_len = self.output_buffer.len();
+
+        if self.next_op()?.is_none() {
+            return Ok(None);
+        }
+
+        Ok(Some(TraceStep {
+            ip,
+            instruction: instruction.unwrap_or_default(),
+            reg_a: self.reg_a,
+            reg_b: self.reg_b,
+            reg_c: self.reg_c,
+            output: self.output_buffer.get(output_len).copied(),
+        }))
+    }
+
+    fn trace(&mut self) -> Result<Vec<TraceStep>, VmError> {
+        let mut steps = Vec::new();
+
+        while let Some(step) = self.traced_op()? {
+            steps.push(step);
+        }
+
+        Ok(steps)
+    }
+
+    fn instruction_at(&self, ip: usize) -> Option<String> {
+        let opcode = *self.program.get(ip)?;
+        let operand = *self.program.get(ip + 1)?;
+
+        let combo_operand = match operand {
+            4 => "A".to_string(),
+            5 => "B".to_string(),
+            6 => "C".to_string(),
+            7 => "<invalid 7>".to_string(),
+            literal => literal.to_string(),
+        };
+
+        let instruction = match opcode {
+            0 => format!("adv {combo_operand}"),
+            1 => format!("bxl {operand}"),
+            2 => format!("bst {combo_operand}"),
+            3 => format!("jnz {operand}"),
+            // bxc reads its operand but ignores it
+            4 => "bxc".to_string(),
+            5 => format!("out {combo_operand}"),
+            6 => format!("bdv {combo_operand}"),
+            7 => format!("cdv {combo_operand}"),
+            opcode => format!("<invalid opcode {opcode}>"),
+        };
+
+        Some(instruction)
+    }
+
+    fn disassemble(&self) -> Vec<String> {
+        (0..self.program.len())
+            .step_by(2)
+            .map(|ip| {
+                let instruction = self
+                    .instruction_at(ip)
+                    .unwrap_or("<missing operand>".to_string());
+                format!("{ip:>3}: {instruction}")
+            })
+            .collect()
+    }
+
+    fn registers(&self) -> String {
+        format!("A={} B={} C={}", self.reg_a, self.reg_b, self.reg_c)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum VmError {
+    InvalidComboOperand { ip: usize },
+    InvalidOpcode { ip: usize, opcode: u128 },
+    MissingOperand { ip: usize },
+}
+
+impl fmt::Display for VmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VmError::InvalidComboOperand { ip } => {
+                write!(f, "Reserved combo operand 7 used at ip {ip}")
+            }
+            VmError::InvalidOpcode { ip, opcode } => {
+                write!(f, "Invalid opcode {opcode} at ip {ip}")
+            }
+            VmError::MissingOperand { ip } => write!(f, "Missing operand at ip {ip}"),
+        }
+    }
+}
+
+struct TraceStep {
+    ip: isize,
+    instruction: String,
+    reg_a: u128,
+    reg_b: u128,
+    reg_c: u128,
+    output: Option<u128>,
+}
+
+impl fmt::Display for TraceStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:>3}: {:<8} A={} B={} C={}",
+            self.ip, self.instruction, self.reg_a, self.reg_b, self.reg_c
+        )?;
+
+        if let Some(output) = self.output {
+            write!(f, " out={output}")?;
+        }
+
+        Ok(())
     }
 }
 
 fn concat_output(file: &str) -> String {
     let mut program = Program::from_file(file);
 
-    while program.next_op().is_some() {}
+    program.run().unwrap_or_else(|error| panic!("{error}"));
 
     program
         .output_buffer
@@ -147,6 +284,198 @@ fn concat_output(file: &str) -> String {
         .join(",")
 }
 
+fn lowest_quine_register(file: &str) -> u128 {
+    // The program is a single loop: each pass computes an output from the low bits of
+    // register A, shifts A right by a few bits, and jumps back to the start until A is 0.
+    // Since B and C are recalculated from A every pass, the last output only depends on the
+    // highest bits of A, the second last on the bits below those, and so on.
+
+    // So A can be built a digit at a time from the last output backwards, keeping the digits
+    // for which the pass produces the wanted output. Trying digits in ascending order means
+    // the first register that reproduces the whole program is the lowest.
+    let program = Program::from_file(file);
+    let shift = loop_shift(&program.program).expect("Program is not a single shifting loop");
+
+    find_quine_register(&program, shift, 0, program.program.len())
+        .expect("No register value reproduces the program")
+}
+
+fn loop_shift(program: &[u128]) -> Option<u128> {
+    let instructions: Vec<(u128, u128)> = program
+        .chunks(2)
+        .map(|instruction| (instruction[0], instruction[1]))
+        .collect();
+
+    // Ends in a jnz back to the start, with no other jumps
+    if instructions.last() != Some(&(3, 0))
+        || instructions
+            .iter()
+            .filter(|(opcode, _)| *opcode == 3)
+            .count()
+            != 1
+    {
+        return None;
+    }
+
+    // A is only ever changed by one adv with a literal operand
+    let mut a_divisions = instructions.iter().filter(|(opcode, _)| *opcode == 0);
+    match (a_divisions.next(), a_divisions.next()) {
+        (Some((_, shift)), None) if *shift < 4 => Some(*shift),
+        _ => None,
+    }
+}
+
+fn find_quine_register(
+    program: &Program,
+    shift: u128,
+    reg_a: u128,
+    remaining: usize,
+) -> Option<u128> {
+    if remaining == 0 {
+        let mut check = program.clone();
+        check.reg_a = reg_a;
+        check.run().ok()?;
+
+        return (check.output_buffer == program.program).then_some(reg_a);
+    }
+
+    let expected_output = program.program[remaining - 1];
+
+    (0..1 << shift)
+        .map(|digit| (reg_a << shift) | digit)
+        .filter(|candidate| first_output(program, *candidate) == Some(expected_output))
+        .find_map(|candidate| find_quine_register(program, shift, candidate, remaining - 1))
+}
+
+fn first_output(program: &Program, reg_a: u128) -> Option<u128> {
+    let mut pass = program.clone();
+    pass.reg_a = reg_a;
+
+    while pass.output_buffer.is_empty() {
+        pass.next_op().ok()??;
+    }
+
+    Some(pass.output_buffer[0])
+}
+
+pub fn trace_program(file: &str, mut output: impl Write) {
+    let mut program = Program::from_file(file);
+
+    match program.trace() {
+        Ok(steps) => {
+            for step in steps {
+                writeln!(output, "{step}").unwrap();
+            }
+            writeln!(output, "Halted").unwrap();
+        }
+        Err(error) => writeln!(output, "Error: {error}").unwrap(),
+    }
+}
+
+pub fn debug(file: &str, input: impl BufRead, mut output: impl Write) {
+    // A small step debugger, reading one command per line from input
+    let mut program = Program::from_file(file);
+    let mut breakpoints: HashSet<isize> = HashSet::new();
+    let mut tracing = false;
+
+    let help = "Commands: s|step [n], c|continue, b|break <ip>, r|regs, d|disasm, \
+                t|trace, set <a|b|c> <value>, reset, q|quit";
+
+    writeln!(output, "{}", program.disassemble().join("\n")).unwrap();
+    writeln!(output, "{help}").unwrap();
+
+    let mut lines = input.lines();
+    loop {
+        write!(output, "(ip {}) > ", program.ip).unwrap();
+        output.flush().unwrap();
+
+        let Some(Ok(line)) = lines.next() else {
+            break;
+        };
+        let command: Vec<&str> = line.split_whitespace().collect();
+
+        match command.as_slice() {
+            ["s" | "step"] | ["s" | "step", _] => {
+                let steps = command.get(1).and_then(|n| n.parse().ok()).unwrap_or(1);
+                for _ in 0..steps {
+                    match program.traced_op() {
+                        Ok(Some(step)) => writeln!(output, "{step}").unwrap(),
+                        Ok(None) => {
+                            writeln!(output, "Halted").unwrap();
+                            break;
+                        }
+                        Err(error) => {
+                            writeln!(output, "Error: {error}").unwrap();
+                            break;
+                        }
+                    }
+                }
+            }
+            ["c" | "continue"] => loop {
+                match program.traced_op() {
+                    Ok(Some(step)) => {
+                        if tracing {
+                            writeln!(output, "{step}").unwrap();
+                        }
+                        if breakpoints.contains(&program.ip) {
+                            writeln!(output, "Breakpoint at ip {}", program.ip).unwrap();
+                            break;
+                        }
+                    }
+                    Ok(None) => {
+                        writeln!(output, "Halted").unwrap();
+                        break;
+                    }
+                    Err(error) => {
+                        writeln!(output, "Error: {error}").unwrap();
+                        break;
+                    }
+                }
+            },
+            ["b" | "break", ip] => match ip.parse::<isize>() {
+                Ok(ip) if breakpoints.remove(&ip) => {
+                    writeln!(output, "Removed breakpoint at ip {ip}").unwrap()
+                }
+                Ok(ip) => {
+                    breakpoints.insert(ip);
+                    writeln!(output, "Added breakpoint at ip {ip}").unwrap()
+                }
+                Err(_) => writeln!(output, "Invalid ip {ip}").unwrap(),
+            },
+            ["r" | "regs"] => {
+                let program_output = program
+                    .output_buffer
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<String>>()
+                    .join(",");
+                writeln!(output, "{} output={program_output}", program.registers()).unwrap();
+            }
+            ["d" | "disasm"] => {
+                for (line, ip) in program.disassemble().into_iter().zip((0..).step_by(2)) {
+                    let current = if ip == program.ip { ">" } else { " " };
+                    let breakpoint = if breakpoints.contains(&ip) { "*" } else { " " };
+                    writeln!(output, "{current}{breakpoint}{line}").unwrap();
+                }
+            }
+            ["t" | "trace"] => {
+                tracing = !tracing;
+                writeln!(output, "Tracing {}", if tracing { "on" } else { "off" }).unwrap();
+            }
+            ["set", register, value] => match (*register, value.parse::<u128>()) {
+                ("a" | "A", Ok(value)) => program.reg_a = value,
+                ("b" | "B", Ok(value)) => program.reg_b = value,
+                ("c" | "C", Ok(value)) => program.reg_c = value,
+                _ => writeln!(output, "Invalid register or value").unwrap(),
+            },
+            ["reset"] => program = Program::from_file(file),
+            ["q" | "quit"] => break,
+            [] => {}
+            _ => writeln!(output, "{help}").unwrap(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -159,6 +488,74 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_lowest_quine_register() {
+        assert_eq!(
+            lowest_quine_register("resources/2024/day17/test_input_2.txt"),
+            117440
+        );
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let program = Program::from_file("resources/2024/day17/input.txt");
+
+        assert_eq!(
+            program.disassemble(),
+            vec![
+                "  0: bst A",
+                "  2: bxl 2",
+                "  4: cdv B",
+                "  6: bxl 7",
+                "  8: bxc",
+                " 10: adv 3",
+                " 12: out B",
+                " 14: jnz 0",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_trace() {
+        let mut program = Program::from_file("resources/2024/day17/test_input.txt");
+        let trace = program.trace().unwrap();
+
+        assert_eq!(trace.len(), 30);
+        assert_eq!(trace[0].to_string(), "  0: adv 1    A=364 B=0 C=0");
+        assert_eq!(trace[1].to_string(), "  2: out A    A=364 B=0 C=0 out=4");
+    }
+
+    #[test]
+    fn test_invalid_combo_operand() {
+        let mut program = Program {
+            ip: 0,
+            program: vec![1, 7, 2, 7],
+            reg_a: 0,
+            reg_b: 0,
+            reg_c: 0,
+            output_buffer: Vec::new(),
+        };
+
+        assert_eq!(program.run(), Err(VmError::InvalidComboOperand { ip: 2 }));
+    }
+
+    #[test]
+    fn test_debug() {
+        let commands = "b 12\nc\nr\ns 2\nq\n";
+        let mut output = Vec::new();
+
+        debug(
+            "resources/2024/day17/input.txt",
+            commands.as_bytes(),
+            &mut output,
+        );
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Breakpoint at ip 12"));
+        assert!(output.contains("A=5205508 B=1301379 C=1301377 output=\n"));
+        assert!(output.contains(" 12: out B    A=5205508 B=1301379 C=1301377 out=3"));
+    }
+
     #[test]
     fn test_program() {
         let mut program = Program {
@@ -170,7 +567,7 @@ mod tests {
             output_buffer: Vec::new(),
         };
 
-        while program.next_op().is_some() {}
+        program.run().unwrap();
 
         assert_eq!(program.reg_b, 1);
     }
@@ -185,7 +582,7 @@ mod tests {
             output_buffer: Vec::new(),
         };
 
-        while program.next_op().is_some() {}
+        program.run().unwrap();
 
         assert_eq!(program.output_buffer, vec![0, 1, 2]);
     }
@@ -0,0 +1,109 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    fs,
+};
+
+pub mod task1 {
+    use super::shortest_path_after;
+
+    pub fn ans() -> u128 {
+        shortest_path_after("resources/2024/day18/input.txt", 71, 1024)
+    }
+}
+
+pub mod task2 {
+    use super::first_blocking_byte;
+
+    pub fn ans() -> String {
+        first_blocking_byte("resources/2024/day18/input.txt", 71).expect("No byte blocks the exit")
+    }
+}
+
+fn shortest_path_after(file: &str, size: isize, bytes: usize) -> u128 {
+    let falling_bytes = parse_file(file);
+
+    shortest_path(&falling_bytes[..bytes], size).expect("No path to the exit") as u128
+}
+
+fn first_blocking_byte(file: &str, size: isize) -> Option<String> {
+    let falling_bytes = parse_file(file);
+
+    // Once the exit is cut off it stays cut off, so binary search for the first
+    // number of fallen bytes which blocks the path
+    let mut low = 0;
+    let mut high = falling_bytes.len();
+    while low < high {
+        let middle = (low + high) / 2;
+        if shortest_path(&falling_bytes[..=middle], size).is_some() {
+            low = middle + 1;
+        } else {
+            high = middle;
+        }
+    }
+
+    // The search ends past the last byte when the exit is never cut off
+    let (x, y) = falling_bytes.get(low)?;
+    Some(format!("{x},{y}"))
+}
+
+fn shortest_path(corrupted: &[(isize, isize)], size: isize) -> Option<usize> {
+    let corrupted: HashSet<&(isize, isize)> = corrupted.iter().collect();
+
+    let end = (size - 1, size - 1);
+    let mut visited = HashSet::from([(0, 0)]);
+    let mut queue = VecDeque::from([((0, 0), 0)]);
+
+    while let Some(((x, y), steps)) = queue.pop_front() {
+        if (x, y) == end {
+            return Some(steps);
+        }
+
+        for next in [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)] {
+            let in_bounds = next.0 >= 0 && next.1 >= 0 && next.0 < size && next.1 < size;
+            if in_bounds && !corrupted.contains(&next) && visited.insert(next) {
+                queue.push_back((next, steps + 1));
+            }
+        }
+    }
+
+    None
+}
+
+fn parse_file(file: &str) -> Vec<(isize, isize)> {
+    let content = fs::read_to_string(file).unwrap();
+
+    content
+        .lines()
+        .map(|line| {
+            let (x, y) = line.split_once(',').unwrap();
+            (x.parse().unwrap(), y.parse().unwrap())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shortest_path_after() {
+        assert_eq!(
+            shortest_path_after("resources/2024/day18/test_input.txt", 7, 12),
+            22
+        );
+    }
+
+    #[test]
+    fn test_first_blocking_byte() {
+        assert_eq!(
+            first_blocking_byte("resources/2024/day18/test_input.txt", 7),
+            Some("6,1".to_string())
+        );
+
+        // The same bytes leave a way round in a larger memory space
+        assert_eq!(
+            first_blocking_byte("resources/2024/day18/test_input.txt", 71),
+            None
+        );
+    }
+}
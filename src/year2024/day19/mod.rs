@@ -0,0 +1,83 @@
+use std::fs;
+
+pub mod task1 {
+    use super::possible_designs;
+
+    pub fn ans() -> u128 {
+        possible_designs("resources/2024/day19/input.txt")
+    }
+}
+
+pub mod task2 {
+    use super::arrangement_count;
+
+    pub fn ans() -> u128 {
+        arrangement_count("resources/2024/day19/input.txt")
+    }
+}
+
+fn possible_designs(file: &str) -> u128 {
+    let (towels, designs) = parse_file(file);
+
+    designs
+        .iter()
+        .filter(|design| arrangements(design, &towels) > 0)
+        .count() as u128
+}
+
+fn arrangement_count(file: &str) -> u128 {
+    let (towels, designs) = parse_file(file);
+
+    designs
+        .iter()
+        .map(|design| arrangements(design, &towels))
+        .sum()
+}
+
+fn arrangements(design: &str, towels: &[String]) -> u128 {
+    // ways[i] is the number of ways to make the first i stripes of the design
+    let mut ways = vec![0; design.len() + 1];
+    ways[0] = 1;
+
+    for i in 0..design.len() {
+        if ways[i] == 0 {
+            continue;
+        }
+
+        for towel in towels {
+            if design[i..].starts_with(towel.as_str()) {
+                ways[i + towel.len()] += ways[i];
+            }
+        }
+    }
+
+    ways[design.len()]
+}
+
+fn parse_file(file: &str) -> (Vec<String>, Vec<String>) {
+    let content = fs::read_to_string(file).unwrap();
+    let (towels, designs) = content.split_once("\n\n").unwrap();
+
+    let towels = towels
+        .split(", ")
+        .map(|towel| towel.trim().to_string())
+        .collect();
+    let designs = designs.lines().map(|design| design.to_string()).collect();
+
+    (towels, designs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_possible_designs() {
+        assert_eq!(possible_designs("resources/2024/day19/test_input.txt"), 6);
+    }
+
+    #[test]
+    fn test_arrangement_count() {
+        assert_eq!(arrangement_count("resources/2024/day19/test_input.txt"), 16);
+    }
+}
@@ -0,0 +1,97 @@
+use std::fs;
+
+pub mod task1 {
+    use super::count_cheats;
+
+    pub fn ans() -> u128 {
+        count_cheats("resources/2024/day20/input.txt", 2, 100)
+    }
+}
+
+pub mod task2 {
+    use super::count_cheats;
+
+    pub fn ans() -> u128 {
+        count_cheats("resources/2024/day20/input.txt", 20, 100)
+    }
+}
+
+fn count_cheats(file: &str, cheat_length: usize, min_saving: usize) -> u128 {
+    let track = parse_file(file);
+
+    // The track is a single path, so a tile's index is its distance from the start.
+    // A cheat between two tiles saves the distance along the track, less the time
+    // spent cheating
+    let mut cheats = 0;
+    for (i, (x1, y1)) in track.iter().enumerate() {
+        for (j, (x2, y2)) in track.iter().enumerate().skip(i + min_saving) {
+            let distance = x1.abs_diff(*x2) + y1.abs_diff(*y2);
+            if distance <= cheat_length && j - i >= min_saving + distance {
+                cheats += 1;
+            }
+        }
+    }
+
+    cheats
+}
+
+fn parse_file(file: &str) -> Vec<(isize, isize)> {
+    let content = fs::read_to_string(file).unwrap();
+
+    let map: Vec<Vec<char>> = content.lines().map(|line| line.chars().collect()).collect();
+    let tile = |(x, y): (isize, isize)| map[y as usize][x as usize];
+
+    let start = map
+        .iter()
+        .enumerate()
+        .flat_map(|(y, row)| row.iter().enumerate().map(move |(x, c)| (x, y, c)))
+        .find(|(_, _, c)| **c == 'S')
+        .map(|(x, y, _)| (x as isize, y as isize))
+        .unwrap();
+
+    let mut track = vec![start];
+    let mut previous = start;
+    let mut current = start;
+    while tile(current) != 'E' {
+        let (x, y) = current;
+        let next = [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)]
+            .into_iter()
+            .find(|next| *next != previous && tile(*next) != '#')
+            .unwrap();
+
+        track.push(next);
+        previous = current;
+        current = next;
+    }
+
+    track
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_cheats() {
+        assert_eq!(
+            count_cheats("resources/2024/day20/test_input.txt", 2, 2),
+            44
+        );
+        assert_eq!(
+            count_cheats("resources/2024/day20/test_input.txt", 2, 20),
+            5
+        );
+    }
+
+    #[test]
+    fn test_count_long_cheats() {
+        assert_eq!(
+            count_cheats("resources/2024/day20/test_input.txt", 20, 76),
+            3
+        );
+        assert_eq!(
+            count_cheats("resources/2024/day20/test_input.txt", 20, 50),
+            285
+        );
+    }
+}
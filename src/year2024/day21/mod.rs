@@ -0,0 +1,129 @@
+use std::{collections::HashMap, fs};
+
+pub mod task1 {
+    use super::complexity_sum;
+
+    pub fn ans() -> u128 {
+        complexity_sum("resources/2024/day21/input.txt", 2)
+    }
+}
+
+pub mod task2 {
+    use super::complexity_sum;
+
+    pub fn ans() -> u128 {
+        complexity_sum("resources/2024/day21/input.txt", 25)
+    }
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Hash)]
+enum Keypad {
+    Numeric,
+    Directional,
+}
+
+impl Keypad {
+    fn position(&self, button: char) -> (isize, isize) {
+        let rows: &[&str] = match self {
+            Keypad::Numeric => &["789", "456", "123", " 0A"],
+            Keypad::Directional => &[" ^A", "<v>"],
+        };
+
+        rows.iter()
+            .enumerate()
+            .find_map(|(y, row)| row.find(button).map(|x| (x as isize, y as isize)))
+            .unwrap()
+    }
+
+    fn gap(&self) -> (isize, isize) {
+        self.position(' ')
+    }
+}
+
+type Cache = HashMap<(Keypad, char, char, usize), u128>;
+
+fn complexity_sum(file: &str, robots: usize) -> u128 {
+    let content = fs::read_to_string(file).unwrap();
+    let mut cache = Cache::new();
+
+    content
+        .lines()
+        .map(|code| {
+            // The numeric keypad's robot is driven through every directional keypad
+            let presses = sequence_cost(code, Keypad::Numeric, robots + 1, &mut cache);
+            let number: u128 = code.trim_end_matches('A').parse().unwrap();
+
+            presses * number
+        })
+        .sum()
+}
+
+// Presses needed on the outermost keypad to type a sequence on a keypad `depth`
+// robots away, starting and ending on A
+fn sequence_cost(sequence: &str, keypad: Keypad, depth: usize, cache: &mut Cache) -> u128 {
+    let mut current = 'A';
+
+    sequence
+        .chars()
+        .map(|button| {
+            let cost = move_cost(keypad, current, button, depth, cache);
+            current = button;
+            cost
+        })
+        .sum()
+}
+
+fn move_cost(keypad: Keypad, from: char, to: char, depth: usize, cache: &mut Cache) -> u128 {
+    if depth == 0 {
+        return 1;
+    }
+    if let Some(cost) = cache.get(&(keypad, from, to, depth)) {
+        return *cost;
+    }
+
+    let (x1, y1) = keypad.position(from);
+    let (x2, y2) = keypad.position(to);
+
+    let horizontal = if x2 > x1 { ">" } else { "<" }.repeat(x1.abs_diff(x2));
+    let vertical = if y2 > y1 { "v" } else { "^" }.repeat(y1.abs_diff(y2));
+
+    // Grouping the presses by direction is always best, leaving only which direction
+    // goes first, as long as that doesn't cross the gap
+    let mut orders = vec![];
+    if keypad.gap() != (x2, y1) {
+        orders.push(format!("{horizontal}{vertical}A"));
+    }
+    if keypad.gap() != (x1, y2) {
+        orders.push(format!("{vertical}{horizontal}A"));
+    }
+
+    let cost = orders
+        .iter()
+        .map(|order| sequence_cost(order, Keypad::Directional, depth - 1, cache))
+        .min()
+        .unwrap();
+
+    cache.insert((keypad, from, to, depth), cost);
+    cost
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_complexity_sum() {
+        assert_eq!(
+            complexity_sum("resources/2024/day21/test_input.txt", 2),
+            126384
+        );
+    }
+
+    #[test]
+    fn test_complexity_sum_with_more_robots() {
+        assert_eq!(
+            complexity_sum("resources/2024/day21/test_input.txt", 25),
+            154115708116294
+        );
+    }
+}
@@ -0,0 +1,104 @@
+use std::fs;
+
+pub mod task1 {
+    use super::sum_secret_numbers;
+
+    pub fn ans() -> u128 {
+        sum_secret_numbers("resources/2024/day22/input.txt")
+    }
+}
+
+pub mod task2 {
+    use super::most_bananas;
+
+    pub fn ans() -> u128 {
+        most_bananas("resources/2024/day22/input.txt")
+    }
+}
+
+const PRUNE: u64 = 16777216;
+
+// Four price changes, each from -9 to 9, packed into a base 19 number
+const SEQUENCES: usize = 19 * 19 * 19 * 19;
+
+fn sum_secret_numbers(file: &str) -> u128 {
+    parse_file(file)
+        .into_iter()
+        .map(|secret| (0..2000).fold(secret, |secret, _| next_secret(secret)) as u128)
+        .sum()
+}
+
+fn most_bananas(file: &str) -> u128 {
+    let mut bananas = vec![0; SEQUENCES];
+    let mut last_buyer = vec![usize::MAX; SEQUENCES];
+
+    for (buyer, mut secret) in parse_file(file).into_iter().enumerate() {
+        let mut sequence = 0;
+        let mut price = secret % 10;
+
+        for i in 0..2000 {
+            secret = next_secret(secret);
+            let next_price = secret % 10;
+
+            let change = (next_price + 9 - price) as usize;
+            sequence = (sequence * 19 + change) % SEQUENCES;
+            price = next_price;
+
+            // The monkey sells at the first time it sees the sequence
+            if i >= 3 && last_buyer[sequence] != buyer {
+                last_buyer[sequence] = buyer;
+                bananas[sequence] += price as u128;
+            }
+        }
+    }
+
+    bananas.into_iter().max().unwrap()
+}
+
+fn next_secret(secret: u64) -> u64 {
+    let secret = ((secret * 64) ^ secret) % PRUNE;
+    let secret = ((secret / 32) ^ secret) % PRUNE;
+    ((secret * 2048) ^ secret) % PRUNE
+}
+
+fn parse_file(file: &str) -> Vec<u64> {
+    let content = fs::read_to_string(file).unwrap();
+
+    content.lines().map(|line| line.parse().unwrap()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_secret() {
+        let secrets: Vec<u64> = (0..10)
+            .scan(123, |secret, _| {
+                *secret = next_secret(*secret);
+                Some(*secret)
+            })
+            .collect();
+
+        assert_eq!(
+            secrets,
+            [
+                15887950, 16495136, 527345, 704524, 1553684, 12683156, 11100544, 12249484, 7753432,
+                5908254
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sum_secret_numbers() {
+        assert_eq!(
+            sum_secret_numbers("resources/2024/day22/test_input.txt"),
+            37327623
+        );
+    }
+
+    #[test]
+    fn test_most_bananas() {
+        assert_eq!(most_bananas("resources/2024/day22/test_input_2.txt"), 23);
+    }
+}
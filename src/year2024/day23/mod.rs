@@ -0,0 +1,137 @@
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fs,
+};
+
+pub mod task1 {
+    use super::chief_historian_triangles;
+
+    pub fn ans() -> u128 {
+        chief_historian_triangles("resources/2024/day23/input.txt")
+    }
+}
+
+pub mod task2 {
+    use super::lan_party_password;
+
+    pub fn ans() -> String {
+        lan_party_password("resources/2024/day23/input.txt")
+    }
+}
+
+type Network = HashMap<String, HashSet<String>>;
+
+fn chief_historian_triangles(file: &str) -> u128 {
+    let network = parse_file(file);
+
+    // Sorting each triangle stops it being counted once per computer
+    let mut triangles = HashSet::new();
+    for (a, neighbours) in network.iter().filter(|(a, _)| a.starts_with('t')) {
+        for b in neighbours {
+            for c in neighbours.intersection(&network[b]) {
+                triangles.insert(BTreeSet::from([a, b, c]));
+            }
+        }
+    }
+
+    triangles.len() as u128
+}
+
+fn lan_party_password(file: &str) -> String {
+    let network = parse_file(file);
+
+    let mut largest = vec![];
+    bron_kerbosch(
+        &network,
+        vec![],
+        network.keys().cloned().collect(),
+        HashSet::new(),
+        &mut largest,
+    );
+
+    largest.sort();
+    largest.join(",")
+}
+
+// Finds every maximal clique, keeping the largest
+fn bron_kerbosch(
+    network: &Network,
+    clique: Vec<String>,
+    mut candidates: HashSet<String>,
+    mut excluded: HashSet<String>,
+    largest: &mut Vec<String>,
+) {
+    if candidates.is_empty() && excluded.is_empty() {
+        if clique.len() > largest.len() {
+            *largest = clique;
+        }
+        return;
+    }
+
+    // Any maximal clique contains the pivot or one of its non-neighbours
+    let pivot = candidates
+        .union(&excluded)
+        .max_by_key(|computer| network[*computer].len())
+        .unwrap()
+        .clone();
+
+    let to_visit: Vec<String> = candidates.difference(&network[&pivot]).cloned().collect();
+
+    for computer in to_visit {
+        let neighbours = &network[&computer];
+
+        let mut next_clique = clique.clone();
+        next_clique.push(computer.clone());
+
+        bron_kerbosch(
+            network,
+            next_clique,
+            candidates.intersection(neighbours).cloned().collect(),
+            excluded.intersection(neighbours).cloned().collect(),
+            largest,
+        );
+
+        candidates.remove(&computer);
+        excluded.insert(computer);
+    }
+}
+
+fn parse_file(file: &str) -> Network {
+    let content = fs::read_to_string(file).unwrap();
+
+    let mut network = Network::new();
+    for line in content.lines() {
+        let (a, b) = line.split_once('-').unwrap();
+        network
+            .entry(a.to_string())
+            .or_default()
+            .insert(b.to_string());
+        network
+            .entry(b.to_string())
+            .or_default()
+            .insert(a.to_string());
+    }
+
+    network
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chief_historian_triangles() {
+        assert_eq!(
+            chief_historian_triangles("resources/2024/day23/test_input.txt"),
+            7
+        );
+    }
+
+    #[test]
+    fn test_lan_party_password() {
+        assert_eq!(
+            lan_party_password("resources/2024/day23/test_input.txt"),
+            "co,de,ka,ta"
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use std::{collections::HashMap, fs};
+
+pub mod task1 {
+    use super::z_output;
+
+    pub fn ans() -> u128 {
+        z_output("resources/2024/day24/input.txt")
+    }
+}
+
+pub mod task2 {
+    use super::swapped_wires;
+
+    pub fn ans() -> String {
+        swapped_wires("resources/2024/day24/input.txt")
+    }
+}
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum Operation {
+    And,
+    Or,
+    Xor,
+}
+
+struct Gate {
+    inputs: [String; 2],
+    operation: Operation,
+    output: String,
+}
+
+impl Gate {
+    fn has_input_bits(&self) -> bool {
+        self.inputs
+            .iter()
+            .all(|input| input.starts_with('x') || input.starts_with('y'))
+    }
+
+    fn has_first_input_bits(&self) -> bool {
+        self.inputs.iter().all(|input| input.ends_with("00"))
+    }
+}
+
+fn z_output(file: &str) -> u128 {
+    let (mut wires, gates) = parse_file(file);
+
+    // Keep passing over the gates until every output has a value
+    let mut remaining: Vec<&Gate> = gates.iter().collect();
+    while !remaining.is_empty() {
+        remaining.retain(|gate| {
+            let (Some(a), Some(b)) = (wires.get(&gate.inputs[0]), wires.get(&gate.inputs[1]))
+            else {
+                return true;
+            };
+
+            let value = match gate.operation {
+                Operation::And => a & b,
+                Operation::Or => a | b,
+                Operation::Xor => a ^ b,
+            };
+            wires.insert(gate.output.clone(), value);
+            false
+        });
+    }
+
+    let mut z_wires: Vec<(&String, &bool)> = wires
+        .iter()
+        .filter(|(wire, _)| wire.starts_with('z'))
+        .collect();
+    z_wires.sort();
+
+    z_wires
+        .into_iter()
+        .rev()
+        .fold(0, |number, (_, bit)| number << 1 | *bit as u128)
+}
+
+fn swapped_wires(file: &str) -> String {
+    let (_, gates) = parse_file(file);
+
+    // The circuit should be a ripple carry adder, where each bit is
+    //   x XOR y -> sum, x AND y -> carry_a
+    //   sum XOR carry_in -> z, sum AND carry_in -> carry_b
+    //   carry_a OR carry_b -> carry_out
+    // with a half adder for the first bit, and the last carry as the top z bit.
+    // Swapped outputs show up as gates wired differently to that
+    let last_z = gates
+        .iter()
+        .map(|gate| &gate.output)
+        .filter(|output| output.starts_with('z'))
+        .max()
+        .unwrap();
+
+    let feeds = |wire: &str, operation: Operation| {
+        gates
+            .iter()
+            .any(|gate| gate.operation == operation && gate.inputs.iter().any(|i| i == wire))
+    };
+
+    let mut swapped: Vec<&str> = gates
+        .iter()
+        .filter(|gate| {
+            let output = gate.output.as_str();
+
+            match gate.operation {
+                _ if output.starts_with('z') && output != last_z => {
+                    gate.operation != Operation::Xor
+                }
+                _ if output == last_z => gate.operation != Operation::Or,
+                Operation::Xor if !gate.has_input_bits() => true,
+                Operation::Xor => !gate.has_first_input_bits() && !feeds(output, Operation::Xor),
+                Operation::And => !gate.has_first_input_bits() && !feeds(output, Operation::Or),
+                Operation::Or => false,
+            }
+        })
+        .map(|gate| gate.output.as_str())
+        .collect();
+
+    swapped.sort();
+    swapped.join(",")
+}
+
+fn parse_file(file: &str) -> (HashMap<String, bool>, Vec<Gate>) {
+    let content = fs::read_to_string(file).unwrap();
+    let (wires, gates) = content.split_once("\n\n").unwrap();
+
+    let wires = wires
+        .lines()
+        .map(|line| {
+            let (wire, value) = line.split_once(": ").unwrap();
+            (wire.to_string(), value == "1")
+        })
+        .collect();
+
+    // Eg. "x00 AND y00 -> z00"
+    let gates = gates
+        .lines()
+        .map(|line| {
+            let [a, operation, b, _, output] = line.split(' ').next_chunk().unwrap();
+            let operation = match operation {
+                "AND" => Operation::And,
+                "OR" => Operation::Or,
+                "XOR" => Operation::Xor,
+                _ => panic!("Invalid operation {operation}"),
+            };
+
+            Gate {
+                inputs: [a.to_string(), b.to_string()],
+                operation,
+                output: output.to_string(),
+            }
+        })
+        .collect();
+
+    (wires, gates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_z_output() {
+        assert_eq!(z_output("resources/2024/day24/test_input.txt"), 4);
+    }
+
+    #[test]
+    fn test_swapped_wires() {
+        // A 4 bit adder with z02 crossed with the carry it should have come from,
+        // and bit 3's sum crossed with its carry
+        assert_eq!(
+            swapped_wires("resources/2024/day24/test_input_2.txt"),
+            "bvc,bwn,cbk,z02"
+        );
+    }
+}
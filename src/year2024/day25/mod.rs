@@ -0,0 +1,65 @@
+use std::fs;
+
+pub mod task1 {
+    use super::fitting_pairs;
+
+    pub fn ans() -> u128 {
+        fitting_pairs("resources/2024/day25/input.txt")
+    }
+}
+
+pub mod task2 {
+    pub fn ans() -> u128 {
+        // Deliver the chronicle!
+        0
+    }
+}
+
+fn fitting_pairs(file: &str) -> u128 {
+    let (locks, keys) = parse_file(file);
+
+    locks
+        .iter()
+        .flat_map(|lock| keys.iter().map(move |key| (lock, key)))
+        .filter(|(lock, key)| lock.iter().zip(key.iter()).all(|(l, k)| l + k <= 5))
+        .count() as u128
+}
+
+fn parse_file(file: &str) -> (Vec<[usize; 5]>, Vec<[usize; 5]>) {
+    let content = fs::read_to_string(file).unwrap();
+
+    let mut locks = vec![];
+    let mut keys = vec![];
+
+    for schematic in content.split("\n\n") {
+        let rows: Vec<&str> = schematic.lines().collect();
+
+        // Heights don't count the full row at the top of a lock or bottom of a key
+        let mut heights = [0; 5];
+        for row in &rows[1..rows.len() - 1] {
+            for (height, c) in heights.iter_mut().zip(row.chars()) {
+                if c == '#' {
+                    *height += 1;
+                }
+            }
+        }
+
+        if rows[0].starts_with('#') {
+            locks.push(heights);
+        } else {
+            keys.push(heights);
+        }
+    }
+
+    (locks, keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fitting_pairs() {
+        assert_eq!(fitting_pairs("resources/2024/day25/test_input.txt"), 3);
+    }
+}